@@ -34,7 +34,7 @@ fn print_names(
     }
 
     // Iterate through all entities with those components.
-    for (name, _) in creepy_hair.iter() {
-        println!("{} has creepy snake hair", name.0);
+    for (name, hair) in creepy_hair.iter() {
+        println!("{} has {} creepy snake hairs", name.0, hair.0);
     }
 }
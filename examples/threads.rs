@@ -1,9 +1,6 @@
 use kudo::*;
 use std::sync::Arc;
 
-#[derive(Debug)]
-struct A {}
-
 fn main() {
     let mut world = World::new();
 
@@ -22,8 +19,7 @@ fn main() {
 
     // Even though this query accesses the same data as the other thread
     // it's ok because both threads are only reading the data.
-    let mut query = world.query::<(&bool,)>().unwrap();
-    for b in query.iter() {
+    for b in world.query::<(&bool,)>().unwrap().iter() {
         println!("Boolean: {:?}", b);
     }
 
@@ -32,9 +28,43 @@ fn main() {
     for i in query.iter() {
         println!("I: {:?}", i);
     }
-
-    // Presently there are not appropriate ways to guarantee queries from other threads won't overlap.
-    // Some sort of scheduling primitives are needed.
+    // Release the query's borrow so the systems below can access `i32`.
+    drop(query);
 
     thread.join().unwrap();
+    // The other thread is done with the world so it can be mutated again.
+    let mut world = Arc::try_unwrap(world).ok().unwrap();
+
+    // Spawning threads by hand doesn't guarantee queries from different threads won't overlap.
+    // A `Schedule` uses each system's parameters to find which systems can safely run at the same time,
+    // and runs those on separate threads.
+    let mut schedule = Schedule::new();
+    schedule
+        .add_system(print_bools)
+        .add_system(increment_numbers)
+        .add_system(print_numbers);
+
+    // `print_bools` and `increment_numbers` run in parallel.
+    // `print_numbers` runs after `increment_numbers` because they both access `i32`.
+    schedule.build().unwrap();
+    println!("Batches: {:?}", schedule.batches());
+    schedule.run(&mut world).unwrap();
+}
+
+fn print_bools(mut query: Query<(&bool,)>) {
+    for b in query.iter() {
+        println!("Boolean: {:?}", b);
+    }
+}
+
+fn increment_numbers(mut query: Query<(&mut i32,)>) {
+    for i in query.iter() {
+        *i += 1;
+    }
+}
+
+fn print_numbers(mut query: Query<(&i32,)>) {
+    for i in query.iter() {
+        println!("I: {:?}", i);
+    }
 }
@@ -0,0 +1,291 @@
+//! Which component types a query or system reads and writes.
+//!
+//! Every `QueryParameter` and `SystemParameter` declares its access up front.
+//! This lets a `Schedule` decide which systems can run at the same time
+//! without taking locks that would fail at runtime, and lets systems and queries
+//! that conflict with themselves be rejected before they run.
+
+use std::any::TypeId;
+
+/// How a component type is accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// Access to a single component type.
+#[derive(Debug, Clone, Copy)]
+pub struct ComponentAccess {
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+    pub kind: AccessKind,
+    /// The name of the parameter that requested this access, used to explain conflicts.
+    pub parameter: &'static str,
+}
+
+impl ComponentAccess {
+    /// Two accesses conflict if they are to the same component and either writes.
+    pub fn conflicts_with(&self, other: &ComponentAccess) -> bool {
+        self.type_id == other.type_id
+            && (self.kind == AccessKind::Write || other.kind == AccessKind::Write)
+    }
+}
+
+/// The set of component types a query or system reads and writes.
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+    components: Vec<ComponentAccess>,
+    /// Access to the entire `World`. Reading it can read any component,
+    /// and writing it can also spawn and despawn entities.
+    world: Option<AccessKind>,
+    /// The name of the parameter that accesses the entire `World`.
+    world_parameter: &'static str,
+    /// Accesses resources that aren't `Send`, so must run on the thread that created the world.
+    non_send: bool,
+}
+
+impl Access {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares read-only access to `T`.
+    pub fn read<T: 'static>(&mut self) {
+        self.push(ComponentAccess {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            kind: AccessKind::Read,
+            parameter: "",
+        });
+    }
+
+    /// Declares read and write access to `T`.
+    pub fn write<T: 'static>(&mut self) {
+        self.push(ComponentAccess {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            kind: AccessKind::Write,
+            parameter: "",
+        });
+    }
+
+    /// Declares read-only access to the whole `World`.
+    /// This conflicts with anything that writes.
+    pub fn read_world(&mut self) {
+        self.world = self.world.max(Some(AccessKind::Read));
+    }
+
+    /// Declares exclusive access to the whole `World`.
+    /// This conflicts with every other access, even an empty one.
+    pub fn write_world(&mut self) {
+        self.world = Some(AccessKind::Write);
+    }
+
+    /// Declares access to a resource that isn't `Send`, so the system runs on the world's thread.
+    /// The resource itself is declared by reading or writing `NonSendMarker<T>`.
+    pub fn non_send(&mut self) {
+        self.non_send = true;
+    }
+
+    /// Returns true if this accesses a resource that isn't `Send`.
+    pub fn is_non_send(&self) -> bool {
+        self.non_send
+    }
+
+    /// How the whole `World` is accessed, if it is.
+    pub fn world(&self) -> Option<AccessKind> {
+        self.world
+    }
+
+    pub fn push(&mut self, access: ComponentAccess) {
+        self.components.push(access);
+    }
+
+    /// Adds everything `other` accesses to this `Access`.
+    pub fn extend(&mut self, other: &Access) {
+        self.components.extend_from_slice(&other.components);
+        self.non_send |= other.non_send;
+        if other.world > self.world {
+            self.world = other.world;
+            self.world_parameter = other.world_parameter;
+        }
+    }
+
+    /// Adds the access of one parameter, recording the parameter's name.
+    /// Accesses already named by a parameter nested inside it, such as in a tuple, keep their names
+    /// so conflicts name the innermost parameters.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct A;
+    /// struct B;
+    ///
+    /// fn nested(parameters: ((Query<(&mut A,)>, &B), &A)) {}
+    ///
+    /// // The conflict is between the `Query` in the inner tuple and the outer `&A`.
+    /// let message = nested.try_system().err().unwrap().to_string();
+    /// assert!(message.contains("Query<'_, (&mut "));
+    /// assert!(message.contains("A,)>` writes ["));
+    /// assert!(message.ends_with("A` reads it"));
+    /// assert!(!message.contains("(("));
+    /// ```
+    pub fn extend_parameter(&mut self, parameter: &'static str, other: &Access) {
+        self.extend_named(
+            other,
+            |inner| if inner.is_empty() { parameter } else { inner },
+        );
+    }
+
+    /// Adds everything `other` accesses without the names of the parameters that requested it,
+    /// so it's named by the parameter it's added to.
+    pub fn extend_unnamed(&mut self, other: &Access) {
+        self.extend_named(other, |_| "");
+    }
+
+    fn extend_named(&mut self, other: &Access, name: impl Fn(&'static str) -> &'static str) {
+        self.components
+            .extend(other.components.iter().map(|access| ComponentAccess {
+                parameter: name(access.parameter),
+                ..*access
+            }));
+        self.non_send |= other.non_send;
+        if other.world > self.world {
+            self.world = other.world;
+            self.world_parameter = name(other.world_parameter);
+        }
+    }
+
+    pub fn components(&self) -> &[ComponentAccess] {
+        &self.components
+    }
+
+    /// The accesses that write to a component.
+    pub fn writes(&self) -> impl Iterator<Item = &ComponentAccess> {
+        self.components
+            .iter()
+            .filter(|access| access.kind == AccessKind::Write)
+    }
+
+    /// Finds two accesses within this `Access` that can't happen at the same time.
+    pub fn conflict(&self) -> Option<AccessConflict> {
+        if self.world == Some(AccessKind::Read) {
+            if let Some(write) = self.writes().next() {
+                return Some(AccessConflict {
+                    system: None,
+                    component: write.type_name,
+                    first: (self.world_parameter, AccessKind::Read),
+                    second: (write.parameter, write.kind),
+                });
+            }
+        }
+        for (i, first) in self.components.iter().enumerate() {
+            for second in self.components[i + 1..].iter() {
+                if first.conflicts_with(second) {
+                    return Some(AccessConflict {
+                        system: None,
+                        component: first.type_name,
+                        first: (first.parameter, first.kind),
+                        second: (second.parameter, second.kind),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// The names of the components this access and `other` conflict over.
+    /// Conflicts from accessing the whole world are named `World`.
+    pub fn conflicting_components(&self, other: &Access) -> Vec<&'static str> {
+        let mut components = Vec::new();
+        let world_conflict = match (self.world, other.world) {
+            (Some(AccessKind::Write), _) | (_, Some(AccessKind::Write)) => true,
+            (Some(AccessKind::Read), _) => other.writes().next().is_some(),
+            (_, Some(AccessKind::Read)) => self.writes().next().is_some(),
+            (None, None) => false,
+        };
+        if world_conflict {
+            components.push("World");
+        }
+        for a in self.components.iter() {
+            if other.components.iter().any(|b| a.conflicts_with(b))
+                && !components.contains(&a.type_name)
+            {
+                components.push(a.type_name);
+            }
+        }
+        components
+    }
+
+    /// Returns true if this access can't happen at the same time as `other`.
+    pub fn conflicts_with(&self, other: &Access) -> bool {
+        if self.world == Some(AccessKind::Write) || other.world == Some(AccessKind::Write) {
+            return true;
+        }
+        if self.world == Some(AccessKind::Read) && other.writes().next().is_some()
+            || other.world == Some(AccessKind::Read) && self.writes().next().is_some()
+        {
+            return true;
+        }
+        self.components
+            .iter()
+            .any(|a| other.components.iter().any(|b| a.conflicts_with(b)))
+    }
+}
+
+/// A system or query that accesses the same component in conflicting ways,
+/// so its borrows would always fail.
+#[derive(Debug, Clone)]
+pub struct AccessConflict {
+    pub(crate) system: Option<&'static str>,
+    component: &'static str,
+    /// The names of the conflicting parameters and how they access the component.
+    first: (&'static str, AccessKind),
+    second: (&'static str, AccessKind),
+}
+
+impl AccessConflict {
+    /// The component type both accesses are for.
+    pub fn component(&self) -> &'static str {
+        self.component
+    }
+}
+
+fn verb(kind: AccessKind) -> &'static str {
+    match kind {
+        AccessKind::Read => "reads",
+        AccessKind::Write => "writes",
+    }
+}
+
+impl std::fmt::Display for AccessConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(system) = self.system {
+            write!(f, "[{}] has conflicting parameters: ", system)?;
+        }
+        let (first_parameter, first_kind) = self.first;
+        let (second_parameter, second_kind) = self.second;
+        if first_parameter == second_parameter {
+            write!(
+                f,
+                "`{}` {} and {} [{}]",
+                first_parameter,
+                verb(first_kind),
+                verb(second_kind),
+                self.component
+            )
+        } else {
+            write!(
+                f,
+                "`{}` {} [{}] while `{}` {} it",
+                first_parameter,
+                verb(first_kind),
+                self.component,
+                second_parameter,
+                verb(second_kind)
+            )
+        }
+    }
+}
+
+impl std::error::Error for AccessConflict {}
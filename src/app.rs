@@ -0,0 +1,124 @@
+//! An `App` owns a `World` and the `Schedule` run on it each frame.
+//! `Plugin`s add their resources, events, component hooks and systems to an `App` in one call.
+
+use crate::{Component, Entity, Event, IntoSystem, Schedule, ScheduleError, World};
+use std::any::TypeId;
+
+/// A group of resources, events, component hooks and systems that can be added to an `App` together.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Gravity(f32);
+/// struct Velocity(f32);
+/// struct Collision;
+/// struct RigidBody;
+///
+/// #[derive(Default)]
+/// struct Bodies(u32);
+///
+/// fn apply_gravity(gravity: &Gravity, mut query: Query<(&mut Velocity,)>) {
+///     for velocity in query.iter() {
+///         velocity.0 -= gravity.0;
+///     }
+/// }
+///
+/// struct PhysicsPlugin;
+///
+/// impl Plugin for PhysicsPlugin {
+///     fn build(&self, app: &mut App) {
+///         app.insert_resource(Gravity(9.8))
+///             .insert_resource(Bodies::default())
+///             .add_event::<Collision>()
+///             .on_add::<RigidBody>(|world, _| world.get_single_mut::<Bodies>().unwrap().0 += 1)
+///             .add_system(apply_gravity);
+///     }
+/// }
+///
+/// let mut app = App::new();
+/// app.add_plugin(PhysicsPlugin);
+/// app.world_mut().spawn((Velocity(0.), RigidBody));
+/// app.update().unwrap();
+/// assert_eq!(app.world().get_single::<Bodies>().unwrap().0, 1);
+/// ```
+pub trait Plugin: 'static {
+    fn build(&self, app: &mut App);
+}
+
+/// A `World` and the `Schedule` that runs on it.
+#[derive(Default)]
+pub struct App {
+    world: World,
+    schedule: Schedule,
+    plugins: Vec<TypeId>,
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a plugin. A plugin that was already added is ignored,
+    /// so plugins can add the plugins they depend on.
+    pub fn add_plugin<T: Plugin>(&mut self, plugin: T) -> &mut Self {
+        let type_id = TypeId::of::<T>();
+        if !self.plugins.contains(&type_id) {
+            self.plugins.push(type_id);
+            plugin.build(self);
+        }
+        self
+    }
+
+    pub fn add_system<P>(&mut self, system: impl IntoSystem<P>) -> &mut Self {
+        self.schedule.add_system(system);
+        self
+    }
+
+    /// Adds a resource, replacing any existing resource of the same type.
+    pub fn insert_resource<T: Component>(&mut self, resource: T) -> &mut Self {
+        self.world.insert_resource(resource);
+        self
+    }
+
+    /// Adds an event type. Its events are updated after each run of the schedule.
+    pub fn add_event<E: Event>(&mut self) -> &mut Self {
+        self.world.add_event::<E>();
+        self
+    }
+
+    /// Adds a hook that runs after a `T` is added to an entity. See `World::on_add`.
+    pub fn on_add<T: Component>(
+        &mut self,
+        hook: impl Fn(&World, Entity) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world.on_add::<T>(hook);
+        self
+    }
+
+    /// Adds a hook that runs before a `T` is removed from an entity. See `World::on_remove`.
+    pub fn on_remove<T: Component>(
+        &mut self,
+        hook: impl Fn(&World, Entity) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world.on_remove::<T>(hook);
+        self
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn schedule_mut(&mut self) -> &mut Schedule {
+        &mut self.schedule
+    }
+
+    /// Runs the schedule once then updates events.
+    pub fn update(&mut self) -> Result<(), ScheduleError> {
+        let result = self.schedule.run(&mut self.world);
+        self.world.update_events();
+        result
+    }
+}
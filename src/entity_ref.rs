@@ -0,0 +1,129 @@
+//! Views of a single entity.
+//!
+//! `World` methods that take an `Entity` check its generation and look up its archetype
+//! on every call. `EntityRef` and `EntityMut` do that once and cache the entity's location.
+
+use crate::world::EntityLocation;
+use crate::{
+    ComponentBundle, ComponentError, ComponentMut, ComponentParameter, ComponentRef, Entity,
+    EntityMissingComponent, World,
+};
+use std::any::TypeId;
+
+/// A read-only view of an entity.
+/// Components are borrowed the same way `World::get` borrows them.
+pub struct EntityRef<'world_borrow> {
+    world: &'world_borrow World,
+    entity: Entity,
+    location: EntityLocation,
+}
+
+impl<'world_borrow> EntityRef<'world_borrow> {
+    pub(crate) fn new(
+        world: &'world_borrow World,
+        entity: Entity,
+        location: EntityLocation,
+    ) -> Self {
+        Self {
+            world,
+            entity,
+            location,
+        }
+    }
+
+    /// The entity this view is of.
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Returns true if the entity has a component of type `T`.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.world.archetypes[self.location.archetype_index as usize].contains(TypeId::of::<T>())
+    }
+
+    /// Gets read-only access to one of the entity's components.
+    pub fn get<T: 'static>(&self) -> Result<ComponentRef<'world_borrow, T>, ComponentError> {
+        <&T>::get_component(self.world, self.entity.index, self.location)
+    }
+
+    /// Gets mutable access to one of the entity's components.
+    /// This fails if the component's storage is already borrowed.
+    pub fn get_mut<T: 'static>(&self) -> Result<ComponentMut<'world_borrow, T>, ComponentError> {
+        <&mut T>::get_component(self.world, self.entity.index, self.location)
+    }
+
+    /// The types of all the entity's components, sorted.
+    pub fn component_type_ids(&self) -> Vec<TypeId> {
+        self.world.archetypes[self.location.archetype_index as usize].type_ids()
+    }
+}
+
+/// A view of an entity with exclusive access to the world.
+/// Components can be added and removed without looking the entity up again.
+pub struct EntityMut<'world_borrow> {
+    world: &'world_borrow mut World,
+    entity: Entity,
+    location: EntityLocation,
+}
+
+impl<'world_borrow> EntityMut<'world_borrow> {
+    pub(crate) fn new(
+        world: &'world_borrow mut World,
+        entity: Entity,
+        location: EntityLocation,
+    ) -> Self {
+        Self {
+            world,
+            entity,
+            location,
+        }
+    }
+
+    /// The entity this view is of.
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Returns true if the entity has a component of type `T`.
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.world.archetypes[self.location.archetype_index as usize].contains(TypeId::of::<T>())
+    }
+
+    /// Gets read-only access to one of the entity's components.
+    pub fn get<T: 'static>(&self) -> Result<ComponentRef<'_, T>, ComponentError> {
+        <&T>::get_component(self.world, self.entity.index, self.location)
+    }
+
+    /// Gets mutable access to one of the entity's components.
+    /// No locks are needed because the world is borrowed exclusively.
+    pub fn get_mut<T: 'static>(&mut self) -> Result<&mut T, EntityMissingComponent> {
+        self.world
+            .get_component_mut_at(self.entity.index, self.location)
+    }
+
+    /// Adds a bundle of components to the entity.
+    /// Components the entity already has are replaced.
+    pub fn insert(&mut self, bundle: impl ComponentBundle) -> &mut Self {
+        self.location = bundle.add_to_entity(self.world, self.entity.index, self.location);
+        self
+    }
+
+    /// Removes a component from the entity and returns it.
+    pub fn remove<T: 'static>(&mut self) -> Result<T, EntityMissingComponent> {
+        let (component, location) = self
+            .world
+            .remove_component_at(self.entity.index, self.location)?;
+        self.location = location;
+        Ok(component)
+    }
+
+    /// Removes the entity and all its components from the world.
+    pub fn despawn(self) {
+        self.world.despawn_at(self.entity.index, self.location);
+    }
+
+    /// The types of all the entity's components, sorted.
+    pub fn component_type_ids(&self) -> Vec<TypeId> {
+        self.world.archetypes[self.location.archetype_index as usize].type_ids()
+    }
+}
@@ -0,0 +1,130 @@
+//! An entry API for a single component of an entity, similar to `HashMap`'s.
+//!
+//! The entity is looked up once when the entry is created. Inserting a vacant entry
+//! migrates the entity to a new archetype at most once.
+
+use crate::world::{Component, EntityId, EntityLocation};
+use crate::World;
+use std::any::TypeId;
+
+/// A view into a single component of an entity, which may or may not exist.
+/// This is constructed with `World::entry`.
+pub enum Entry<'world_borrow, T> {
+    Occupied(OccupiedEntry<'world_borrow, T>),
+    Vacant(VacantEntry<'world_borrow, T>),
+}
+
+impl<'world_borrow, T: Component> Entry<'world_borrow, T> {
+    pub(crate) fn new(
+        world: &'world_borrow mut World,
+        entity_index: EntityId,
+        location: EntityLocation,
+    ) -> Self {
+        if world.archetypes[location.archetype_index as usize].contains(TypeId::of::<T>()) {
+            Entry::Occupied(OccupiedEntry {
+                world,
+                entity_index,
+                location,
+                phantom: std::marker::PhantomData,
+            })
+        } else {
+            Entry::Vacant(VacantEntry {
+                world,
+                entity_index,
+                location,
+                phantom: std::marker::PhantomData,
+            })
+        }
+    }
+
+    /// Inserts `default` if the entity does not have the component.
+    /// Returns a mutable reference to the component.
+    pub fn or_insert(self, default: T) -> &'world_borrow mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Inserts the result of `default` if the entity does not have the component.
+    /// `default` is only called if the component is missing.
+    pub fn or_insert_with(self, default: impl FnOnce() -> T) -> &'world_borrow mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Calls `f` on the component if the entity has it.
+    pub fn and_modify(mut self, f: impl FnOnce(&mut T)) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'world_borrow, T: Component + Default> Entry<'world_borrow, T> {
+    /// Inserts `T::default()` if the entity does not have the component.
+    pub fn or_default(self) -> &'world_borrow mut T {
+        self.or_insert_with(T::default)
+    }
+}
+
+/// An entry for a component the entity has.
+pub struct OccupiedEntry<'world_borrow, T> {
+    world: &'world_borrow mut World,
+    entity_index: EntityId,
+    location: EntityLocation,
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<'world_borrow, T: Component> OccupiedEntry<'world_borrow, T> {
+    /// Gets mutable access to the component.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.world
+            .get_component_mut_at(self.entity_index, self.location)
+            .unwrap()
+    }
+
+    /// Converts the entry into a mutable reference to the component that lives as long as the world borrow.
+    pub fn into_mut(self) -> &'world_borrow mut T {
+        self.world
+            .get_component_mut_at(self.entity_index, self.location)
+            .unwrap()
+    }
+
+    /// Replaces the component and returns the previous value.
+    pub fn insert(&mut self, t: T) -> T {
+        std::mem::replace(self.get_mut(), t)
+    }
+
+    /// Removes the component from the entity and returns it.
+    pub fn remove(self) -> T {
+        let (component, _) = self
+            .world
+            .remove_component_at(self.entity_index, self.location)
+            .unwrap();
+        component
+    }
+}
+
+/// An entry for a component the entity does not have.
+pub struct VacantEntry<'world_borrow, T> {
+    world: &'world_borrow mut World,
+    entity_index: EntityId,
+    location: EntityLocation,
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<'world_borrow, T: Component> VacantEntry<'world_borrow, T> {
+    /// Adds the component to the entity and returns a mutable reference to it.
+    pub fn insert(self, t: T) -> &'world_borrow mut T {
+        let (_, location) = self
+            .world
+            .insert_component_at(self.entity_index, self.location, t);
+        self.world
+            .get_component_mut_at(self.entity_index, location)
+            .unwrap()
+    }
+}
@@ -1,7 +1,51 @@
+use crate::AccessConflict;
+
 #[derive(Debug)]
 pub enum FetchError {
     ComponentAlreadyBorrowed(ComponentAlreadyBorrowed),
     ComponentDoesNotExist(ComponentDoesNotExist),
+    AccessConflict(AccessConflict),
+    NonSendWrongThread(NonSendWrongThread),
+    ExclusiveSystem(ExclusiveSystem),
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::ComponentAlreadyBorrowed(e) => e.fmt(f),
+            FetchError::ComponentDoesNotExist(e) => e.fmt(f),
+            FetchError::AccessConflict(e) => e.fmt(f),
+            FetchError::NonSendWrongThread(e) => e.fmt(f),
+            FetchError::ExclusiveSystem(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// An error from running a system.
+/// Either the system's parameters could not be fetched or the system itself returned an error.
+#[derive(Debug)]
+pub enum SystemError<E = Box<dyn std::error::Error + Send + Sync>> {
+    Fetch(FetchError),
+    System(E),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for SystemError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SystemError::Fetch(e) => e.fmt(f),
+            SystemError::System(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: std::fmt::Debug + std::fmt::Display> std::error::Error for SystemError<E> {}
+
+impl<E> From<FetchError> for SystemError<E> {
+    fn from(e: FetchError) -> Self {
+        SystemError::Fetch(e)
+    }
 }
 
 #[derive(Debug)]
@@ -37,3 +81,47 @@ impl std::fmt::Display for ComponentDoesNotExist {
 }
 
 impl std::error::Error for ComponentDoesNotExist {}
+
+/// Non-`Send` resources can only be accessed from the thread that created the world.
+#[derive(Debug)]
+pub struct NonSendWrongThread(&'static str);
+
+impl NonSendWrongThread {
+    pub fn new<T>() -> Self {
+        Self(std::any::type_name::<T>())
+    }
+}
+
+impl std::fmt::Display for NonSendWrongThread {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] is not Send and can only be accessed from the thread that created the world",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for NonSendWrongThread {}
+
+/// Exclusive systems take `&mut World` so they can't be run with a shared `&World`.
+#[derive(Debug)]
+pub struct ExclusiveSystem(&'static str);
+
+impl ExclusiveSystem {
+    pub fn new(system: &'static str) -> Self {
+        Self(system)
+    }
+}
+
+impl std::fmt::Display for ExclusiveSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] requires exclusive access to the World so it must be run with `run_exclusive`",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ExclusiveSystem {}
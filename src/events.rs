@@ -0,0 +1,210 @@
+//! Typed events that systems send to each other.
+//!
+//! Events of type `E` are stored in the `Events<E>` resource, added with `World::add_event`.
+//! Events are double-buffered: `World::update_events` swaps the buffers, so every event
+//! can be read until it has been through two updates and then it's dropped.
+//! Each `EventReader` remembers which events it has read so it only sees new ones.
+
+use crate::query::{Single, SingleMut};
+use crate::world::LockMode;
+use crate::{Access, Component, Fetch, FetchError, FetchItem, SystemParameter, World};
+
+/// Types that can be sent as events.
+pub trait Event: Component {}
+
+impl<T: Component> Event for T {}
+
+/// A resource storing events of one type.
+pub struct Events<E: Event> {
+    /// Events sent before the last update.
+    previous: Vec<E>,
+    /// The id of the first event in `previous`.
+    previous_start: usize,
+    /// Events sent since the last update.
+    current: Vec<E>,
+    current_start: usize,
+}
+
+impl<E: Event> Default for Events<E> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            previous_start: 0,
+            current: Vec::new(),
+            current_start: 0,
+        }
+    }
+}
+
+impl<E: Event> Events<E> {
+    pub fn send(&mut self, event: E) {
+        self.current.push(event);
+    }
+
+    /// Drops events sent before the previous update and starts a new buffer.
+    pub fn update(&mut self) {
+        self.previous_start = self.current_start;
+        self.current_start += self.current.len();
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    /// The id the next event sent will have.
+    fn next_id(&self) -> usize {
+        self.current_start + self.current.len()
+    }
+
+    /// Events with ids from `first_id` onwards that haven't been dropped.
+    fn since(&self, first_id: usize) -> impl Iterator<Item = &E> {
+        let skip_previous = first_id.saturating_sub(self.previous_start);
+        let skip_current = first_id.saturating_sub(self.current_start);
+        self.previous
+            .iter()
+            .skip(skip_previous)
+            .chain(self.current.iter().skip(skip_current))
+    }
+}
+
+pub(crate) fn update_events<E: Event>(world: &mut World) {
+    if let Ok(mut events) = world.get_single_mut::<Events<E>>() {
+        events.update();
+    }
+}
+
+/// A system parameter for sending events.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct DamageDealt(u32);
+///
+/// fn attack(mut damage: EventWriter<DamageDealt>) {
+///     damage.send(DamageDealt(10));
+/// }
+///
+/// fn report(mut damage: EventReader<DamageDealt>) {
+///     for event in damage.iter() {
+///         println!("{} damage dealt", event.0);
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.add_event::<DamageDealt>();
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_chain(vec![attack.system(), report.system()]);
+/// schedule.run(&mut world).unwrap();
+/// world.update_events();
+/// ```
+pub struct EventWriter<'a, E: Event> {
+    events: &'a mut Events<E>,
+}
+
+impl<'a, E: Event> EventWriter<'a, E> {
+    pub fn send(&mut self, event: E) {
+        self.events.send(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = E>) {
+        for event in events {
+            self.events.send(event);
+        }
+    }
+}
+
+impl<'a, E: Event> SystemParameter for EventWriter<'a, E> {
+    type State = ();
+    type Fetch = EventWriterFetch<E>;
+    fn access(access: &mut Access) {
+        access.write::<Events<E>>()
+    }
+}
+
+pub struct EventWriterFetch<E> {
+    phantom: std::marker::PhantomData<E>,
+}
+
+impl<'world_borrow, E: Event> Fetch<'world_borrow> for EventWriterFetch<E> {
+    type Item = EventWriterItem<'world_borrow, E>;
+    fn fetch(
+        world: &'world_borrow World,
+        _state: &'world_borrow mut (),
+    ) -> Result<Self::Item, FetchError> {
+        Ok(EventWriterItem {
+            events: SingleMut::fetch(world, LockMode::Try)?,
+        })
+    }
+}
+
+pub struct EventWriterItem<'world_borrow, E: Event> {
+    events: SingleMut<'world_borrow, Events<E>>,
+}
+
+impl<'a, 'world_borrow, E: Event> FetchItem<'a> for EventWriterItem<'world_borrow, E> {
+    type InnerItem = EventWriter<'a, E>;
+    fn inner(&'a mut self) -> Self::InnerItem {
+        EventWriter {
+            events: &mut self.events,
+        }
+    }
+}
+
+/// A system parameter for reading events.
+/// Each system's reader only returns events it hasn't returned before.
+pub struct EventReader<'a, E: Event> {
+    events: &'a Events<E>,
+    /// The id of the next event to read.
+    cursor: &'a mut usize,
+}
+
+impl<'a, E: Event> EventReader<'a, E> {
+    /// Iterates over events sent since this system last read them.
+    pub fn iter(&mut self) -> impl Iterator<Item = &'a E> {
+        let first_id = *self.cursor;
+        *self.cursor = self.events.next_id();
+        self.events.since(first_id)
+    }
+
+    /// Returns true if there are no events this system hasn't read.
+    pub fn is_empty(&self) -> bool {
+        self.events.since(*self.cursor).next().is_none()
+    }
+}
+
+impl<'a, E: Event> SystemParameter for EventReader<'a, E> {
+    type State = usize;
+    type Fetch = EventReaderFetch<E>;
+    fn access(access: &mut Access) {
+        access.read::<Events<E>>()
+    }
+}
+
+pub struct EventReaderFetch<E> {
+    phantom: std::marker::PhantomData<E>,
+}
+
+impl<'world_borrow, E: Event> Fetch<'world_borrow, usize> for EventReaderFetch<E> {
+    type Item = EventReaderItem<'world_borrow, E>;
+    fn fetch(
+        world: &'world_borrow World,
+        cursor: &'world_borrow mut usize,
+    ) -> Result<Self::Item, FetchError> {
+        Ok(EventReaderItem {
+            events: Single::fetch(world, LockMode::Try)?,
+            cursor,
+        })
+    }
+}
+
+pub struct EventReaderItem<'world_borrow, E: Event> {
+    events: Single<'world_borrow, Events<E>>,
+    cursor: &'world_borrow mut usize,
+}
+
+impl<'a, 'world_borrow, E: Event> FetchItem<'a> for EventReaderItem<'world_borrow, E> {
+    type InnerItem = EventReader<'a, E>;
+    fn inner(&'a mut self) -> Self::InnerItem {
+        EventReader {
+            events: &self.events,
+            cursor: &mut *self.cursor,
+        }
+    }
+}
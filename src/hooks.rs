@@ -0,0 +1,59 @@
+//! Component hooks run when a component is added to or removed from an entity,
+//! such as to keep an index of entities by component up to date.
+//!
+//! On-add hooks run after the component is added and on-remove hooks run before it's removed,
+//! so both can read the component. Hooks get a shared `&World`. They can read and write
+//! components but can't add or remove them, which would move entities that an `EntityMut`
+//! or `Entry` is in the middle of changing.
+
+use crate::{Entity, World};
+use std::any::TypeId;
+use std::collections::HashMap;
+
+type ComponentHook = Box<dyn Fn(&World, Entity) + Send + Sync>;
+
+/// Every hook added to a world, by component type.
+#[derive(Default)]
+pub(crate) struct ComponentHooks {
+    on_add: HashMap<TypeId, Vec<ComponentHook>>,
+    on_remove: HashMap<TypeId, Vec<ComponentHook>>,
+}
+
+impl ComponentHooks {
+    pub(crate) fn add_on_add(&mut self, type_id: TypeId, hook: ComponentHook) {
+        self.on_add.entry(type_id).or_default().push(hook);
+    }
+
+    pub(crate) fn add_on_remove(&mut self, type_id: TypeId, hook: ComponentHook) {
+        self.on_remove.entry(type_id).or_default().push(hook);
+    }
+
+    pub(crate) fn has_on_add(&self) -> bool {
+        !self.on_add.is_empty()
+    }
+
+    pub(crate) fn has_on_remove(&self) -> bool {
+        !self.on_remove.is_empty()
+    }
+
+    pub(crate) fn run_on_add(&self, world: &World, type_id: TypeId, entity: Entity) {
+        run(&self.on_add, world, type_id, entity)
+    }
+
+    pub(crate) fn run_on_remove(&self, world: &World, type_id: TypeId, entity: Entity) {
+        run(&self.on_remove, world, type_id, entity)
+    }
+}
+
+fn run(
+    hooks: &HashMap<TypeId, Vec<ComponentHook>>,
+    world: &World,
+    type_id: TypeId,
+    entity: Entity,
+) {
+    if let Some(hooks) = hooks.get(&type_id) {
+        for hook in hooks {
+            hook(world, entity);
+        }
+    }
+}
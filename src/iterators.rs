@@ -12,6 +12,7 @@ macro_rules! impl_zip {
 
         impl<A: Iterator, $($T: Iterator,)*> $name<A, $($T,)*> {
             #[allow(non_snake_case)]
+            #[allow(clippy::too_many_arguments)]
             pub fn new (A: A, $($T: $T,)*) -> Self {
                 Self {
                     inner: A$(.zip($T))*
@@ -46,6 +47,20 @@ impl_zip! {Zip8, Zip<Zip<Zip<Zip<Zip<Zip<Zip<A, B>, C>, D>, E>, F>, G>, H>, |(((
 
 #[doc(hidden)]
 /// A series of iterators of the same type that are traversed in a row.
+///
+/// Queries chain an iterator per archetype, and archetypes whose entities
+/// were all despawned are skipped however many are in a row.
+/// ```
+/// # use kudo::*;
+/// let mut world = World::new();
+/// world.spawn((1u32, true));
+/// let despawned = world.spawn((2u32, 'c'));
+/// world.spawn((3u32, "three".to_string()));
+/// world.despawn(despawned).unwrap();
+///
+/// let mut query = world.query::<(&u32,)>().unwrap();
+/// assert_eq!(query.iter().count(), 2);
+/// ```
 pub struct ChainedIterator<I: Iterator> {
     current_iter: Option<I>,
     iterators: Vec<I>,
@@ -69,20 +84,15 @@ impl<I: Iterator> Iterator for ChainedIterator<I> {
     fn next(&mut self) -> Option<Self::Item> {
         // Chain the iterators together.
         // If the end of one iterator is reached go to the next.
-
-        match self.current_iter {
-            Some(ref mut iter) => match iter.next() {
-                None => {
-                    self.current_iter = self.iterators.pop();
-                    if let Some(ref mut iter) = self.current_iter {
-                        iter.next()
-                    } else {
-                        None
-                    }
-                }
-                item => item,
-            },
-            None => None,
+        // Several iterators in a row may be empty so keep going until an item is found.
+        loop {
+            match self.current_iter {
+                Some(ref mut iter) => match iter.next() {
+                    None => self.current_iter = self.iterators.pop(),
+                    item => return item,
+                },
+                None => return None,
+            }
         }
     }
 
@@ -37,17 +37,41 @@
 //! }
 //! ```
 
+mod access;
+mod app;
 mod iterators;
+mod non_send;
 //mod query;
+mod entity_ref;
+mod entry;
 mod errors;
+mod events;
+mod hooks;
 mod query;
+mod read_only_world;
+mod schedule;
+mod state;
+mod stats;
 mod system;
+mod system_parameter;
+mod time;
 mod world;
 
+pub use access::*;
+pub use app::*;
+pub use entity_ref::*;
+pub use entry::*;
 pub use errors::*;
+pub use events::*;
 pub use iterators::*;
+pub use non_send::*;
 pub use query::Query;
 pub use query::*;
 //pub use query::*;
+pub use read_only_world::*;
+pub use schedule::*;
+pub use state::*;
+pub use stats::*;
 pub use system::*;
+pub use time::*;
 pub use world::*;
@@ -0,0 +1,200 @@
+//! Resources that can't be sent between threads, such as `Rc`s, `Cell`s and window handles.
+//!
+//! A `World` is shared between the threads running a `Schedule` so everything in it must be `Send + Sync`.
+//! Non-`Send` resources are instead stored in a thread local of the thread that created the world,
+//! and can only be accessed from that thread.
+//! Systems that access them declare it in their `Access`, and a `Schedule` runs them on the thread
+//! that called `Schedule::run`, which must be the thread that created the world.
+
+use crate::{
+    Access, ComponentDoesNotExist, Fetch, FetchError, FetchItem, NonSendWrongThread,
+    SystemParameter, World,
+};
+use std::any::{Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+thread_local! {
+    /// Non-`Send` resources of every world created on this thread, by world id and type.
+    static NON_SEND: RefCell<HashMap<(u64, TypeId), Rc<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+pub(crate) fn insert<T: 'static>(world_id: u64, value: T) {
+    NON_SEND.with(|resources| {
+        resources
+            .borrow_mut()
+            .insert((world_id, TypeId::of::<T>()), Rc::new(RefCell::new(value)))
+    });
+}
+
+/// Gets a resource from the current thread.
+fn get<T: 'static>(world_id: u64) -> Option<Rc<RefCell<T>>> {
+    NON_SEND.with(|resources| {
+        resources
+            .borrow()
+            .get(&(world_id, TypeId::of::<T>()))
+            .cloned()
+            .map(|resource| resource.downcast::<RefCell<T>>().unwrap())
+    })
+}
+
+/// Drops every resource of a world from the current thread.
+pub(crate) fn remove_world(world_id: u64) {
+    // The thread local may already be gone if the world is dropped while its thread exits.
+    let _ =
+        NON_SEND.try_with(|resources| resources.borrow_mut().retain(|(id, _), _| *id != world_id));
+}
+
+fn fetch<T: 'static>(world: &World) -> Result<Rc<RefCell<T>>, FetchError> {
+    if std::thread::current().id() != world.thread() {
+        return Err(FetchError::NonSendWrongThread(
+            NonSendWrongThread::new::<T>(),
+        ));
+    }
+    get(world.id())
+        .ok_or_else(|| FetchError::ComponentDoesNotExist(ComponentDoesNotExist::new::<T>()))
+}
+
+/// The type a non-`Send` resource `T` is declared as in an `Access`,
+/// so that it doesn't conflict with components of type `T`.
+pub struct NonSendMarker<T>(PhantomData<T>);
+
+/// A system parameter that reads a resource added with `World::insert_non_send`.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// use std::rc::Rc;
+///
+/// struct Window {
+///     title: Rc<String>,
+/// }
+///
+/// fn print_title(window: NonSend<Window>) {
+///     println!("{}", window.title);
+/// }
+///
+/// let mut world = World::new();
+/// world.insert_non_send(Window {
+///     title: Rc::new("Game".to_string()),
+/// });
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(print_title);
+/// schedule.run(&mut world).unwrap();
+///
+/// // The schedule can't run on another thread.
+/// std::thread::spawn(move || {
+///     let error = schedule.run(&mut world).unwrap_err();
+///     assert!(error.to_string().contains("print_title"));
+///     assert!(matches!(error, ScheduleError::NonSendWrongThread { .. }));
+/// })
+/// .join()
+/// .unwrap();
+/// ```
+pub struct NonSend<'a, T> {
+    value: Ref<'a, T>,
+}
+
+impl<'a, T> Deref for NonSend<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T: 'static> SystemParameter for NonSend<'a, T> {
+    type State = ();
+    type Fetch = NonSendFetch<T>;
+    fn access(access: &mut Access) {
+        access.read::<NonSendMarker<T>>();
+        access.non_send();
+    }
+}
+
+/// A system parameter that reads and writes a resource added with `World::insert_non_send`.
+pub struct NonSendMut<'a, T> {
+    value: RefMut<'a, T>,
+}
+
+impl<'a, T> Deref for NonSendMut<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T> DerefMut for NonSendMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<'a, T: 'static> SystemParameter for NonSendMut<'a, T> {
+    type State = ();
+    type Fetch = NonSendMutFetch<T>;
+    fn access(access: &mut Access) {
+        access.write::<NonSendMarker<T>>();
+        access.non_send();
+    }
+}
+
+pub struct NonSendFetch<T> {
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<'world_borrow, T: 'static> Fetch<'world_borrow> for NonSendFetch<T> {
+    type Item = NonSendItem<T>;
+    fn fetch(
+        world: &'world_borrow World,
+        _state: &'world_borrow mut (),
+    ) -> Result<Self::Item, FetchError> {
+        Ok(NonSendItem {
+            value: fetch(world)?,
+        })
+    }
+}
+
+pub struct NonSendMutFetch<T> {
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<'world_borrow, T: 'static> Fetch<'world_borrow> for NonSendMutFetch<T> {
+    type Item = NonSendMutItem<T>;
+    fn fetch(
+        world: &'world_borrow World,
+        _state: &'world_borrow mut (),
+    ) -> Result<Self::Item, FetchError> {
+        Ok(NonSendMutItem {
+            value: fetch(world)?,
+        })
+    }
+}
+
+pub struct NonSendItem<T> {
+    value: Rc<RefCell<T>>,
+}
+
+impl<'a, T: 'a> FetchItem<'a> for NonSendItem<T> {
+    type InnerItem = NonSend<'a, T>;
+    fn inner(&'a mut self) -> Self::InnerItem {
+        NonSend {
+            value: self.value.borrow(),
+        }
+    }
+}
+
+pub struct NonSendMutItem<T> {
+    value: Rc<RefCell<T>>,
+}
+
+impl<'a, T: 'a> FetchItem<'a> for NonSendMutItem<T> {
+    type InnerItem = NonSendMut<'a, T>;
+    fn inner(&'a mut self) -> Self::InnerItem {
+        NonSendMut {
+            value: self.value.borrow_mut(),
+        }
+    }
+}
@@ -9,43 +9,213 @@
 //!
 //! `FetchItem` exists so that RwLocks can be held in the scope that calls the user system.
 //! but the user system receives a simple &T or &mut T.
+//!
+//! Zero-sized tag components have no column so they're borrowed from the world's tag pool instead.
+//! Every `&mut T` needs an instance of its own, so mutable borrows move instances out of the pool
+//! until they're dropped.
 
 use crate::iterators::*;
+use crate::world::{is_tag, EntityId, LockMode, TagsMut};
 use crate::{
-    Archetype, ChainedIterator, ComponentAlreadyBorrowed, ComponentDoesNotExist, FetchError, World,
+    Access, Archetype, ChainedIterator, ComponentAlreadyBorrowed, ComponentDoesNotExist,
+    ComponentError, EntityLocation, EntityMissingComponent, FetchError, World,
 };
+use std::any::TypeId;
 use std::iter::Zip;
-use std::sync::{RwLockReadGuard, RwLockWriteGuard};
-use std::{any::TypeId, usize};
+use std::ops::{Deref, DerefMut};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
+/// Something a system can take as an argument.
+///
+/// Tuples of parameters are parameters too, so a system can take more than 12 parameters
+/// and helpers can take a group of parameters as one argument.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Position(f32);
+/// struct Speed(f32);
+///
+/// fn movement((mut query, speed): (Query<(&mut Position,)>, &Speed)) {
+///     for position in query.iter() {
+///         position.0 += speed.0;
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn((Position(0.),));
+/// world.spawn((Speed(1.),));
+/// movement.run(&world).unwrap();
+/// ```
 pub trait SystemParameter {
+    /// Data a system keeps for this parameter between runs. Most parameters use `()`.
+    type State: Default + Send + Sync + 'static;
     // This is used to specify how and what to request from the World.
-    type Fetch: for<'a> Fetch<'a>;
+    type Fetch: for<'a> Fetch<'a, Self::State>;
+    /// Declares which components this parameter reads and writes.
+    fn access(access: &mut Access);
 }
 
 impl<'a, T: QueryParameters> SystemParameter for Query<'a, T> {
+    type State = ();
     type Fetch = QueryFetch<T>;
+    fn access(access: &mut Access) {
+        // The query's parameters name their own accesses, but within a system
+        // the whole query is one parameter, which is named by whatever holds it.
+        let mut query_access = Access::new();
+        T::access(&mut query_access);
+        access.extend_unnamed(&query_access);
+    }
 }
 
 impl<T: 'static> SystemParameter for &T {
+    type State = ();
     type Fetch = Self;
+    fn access(access: &mut Access) {
+        access.read::<T>()
+    }
 }
 
 impl<T: 'static> SystemParameter for &mut T {
+    type State = ();
     type Fetch = Self;
+    fn access(access: &mut Access) {
+        access.write::<T>()
+    }
+}
+
+/// State that belongs to a single system and persists between its runs.
+/// The value starts as `T::default()` and is not visible to other systems,
+/// so it never conflicts with another parameter.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// fn count_runs(mut runs: Local<usize>) {
+///     *runs += 1;
+///     println!("Run {} times", *runs);
+/// }
+///
+/// let mut world = World::new();
+/// let mut system = count_runs.system();
+/// system.run(&mut world).unwrap();
+/// system.run(&mut world).unwrap();
+/// ```
+pub struct Local<'state, T> {
+    value: &'state mut T,
+}
+
+impl<'state, T> Deref for Local<'state, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<'state, T> DerefMut for Local<'state, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+impl<'a, T: Default + Send + Sync + 'static> SystemParameter for Local<'a, T> {
+    type State = T;
+    type Fetch = LocalFetch<T>;
+    fn access(_access: &mut Access) {}
+}
+
+pub struct LocalFetch<T> {
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<'state, T: 'static> Fetch<'state, T> for LocalFetch<T> {
+    type Item = Option<Local<'state, T>>;
+    fn fetch(_world: &'state World, state: &'state mut T) -> Result<Self::Item, FetchError> {
+        Ok(Some(Local { value: state }))
+    }
+}
+
+impl<'a, 'state, T> FetchItem<'a> for Option<Local<'state, T>> {
+    type InnerItem = Local<'state, T>;
+    fn inner(&'a mut self) -> Self::InnerItem {
+        self.take().unwrap()
+    }
+}
+
+// A tuple of parameters is itself a parameter, so parameters can be grouped and nested.
+// Its parameters are fetched in order and their access is checked with the rest of the system's.
+macro_rules! system_parameter_tuple_impl {
+    ($(($name: ident, $state: ident)),*) => {
+        impl<$($name: SystemParameter,)*> SystemParameter for ($($name,)*) {
+            type State = ($($name::State,)*);
+            type Fetch = ($($name::Fetch,)*);
+            fn access(access: &mut Access) {
+                $(
+                    let mut parameter_access = Access::new();
+                    $name::access(&mut parameter_access);
+                    access.extend_parameter(std::any::type_name::<$name>(), &parameter_access);
+                )*
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<'world_borrow, $($state, $name: Fetch<'world_borrow, $state>,)*>
+            Fetch<'world_borrow, ($($state,)*)> for ($($name,)*)
+        {
+            type Item = ($($name::Item,)*);
+            fn fetch(
+                world: &'world_borrow World,
+                state: &'world_borrow mut ($($state,)*),
+            ) -> Result<Self::Item, FetchError> {
+                let ($($name,)*) = state;
+                Ok(($($name::fetch(world, $name)?,)*))
+            }
+
+            fn entity_counts(item: &Self::Item, counts: &mut Vec<usize>) {
+                let ($($name,)*) = item;
+                $($name::entity_counts($name, counts);)*
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<'a, $($name: FetchItem<'a>,)*> FetchItem<'a> for ($($name,)*) {
+            type InnerItem = ($($name::InnerItem,)*);
+            fn inner(&'a mut self) -> Self::InnerItem {
+                let ($($name,)*) = self;
+                ($($name.inner(),)*)
+            }
+        }
+    };
 }
 
+system_parameter_tuple_impl! {(A, SA)}
+system_parameter_tuple_impl! {(A, SA), (B, SB)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC), (D, SD)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC), (D, SD), (E, SE)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC), (D, SD), (E, SE), (F, SF)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC), (D, SD), (E, SE), (F, SF), (G, SG)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC), (D, SD), (E, SE), (F, SF), (G, SG), (H, SH)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC), (D, SD), (E, SE), (F, SF), (G, SG), (H, SH), (I, SI)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC), (D, SD), (E, SE), (F, SF), (G, SG), (H, SH), (I, SI), (J, SJ)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC), (D, SD), (E, SE), (F, SF), (G, SG), (H, SH), (I, SI), (J, SJ), (K, SK)}
+system_parameter_tuple_impl! {(A, SA), (B, SB), (C, SC), (D, SD), (E, SE), (F, SF), (G, SG), (H, SH), (I, SI), (J, SJ), (K, SK), (L, SL)}
+
 pub struct QueryFetch<T> {
     phantom: std::marker::PhantomData<T>,
 }
 
 impl<'world_borrow, T: QueryParameters> Fetch<'world_borrow> for QueryFetch<T> {
     type Item = Option<Query<'world_borrow, T>>;
-    fn fetch(world: &'world_borrow World) -> Result<Self::Item, FetchError> {
-        Ok(Some(Query {
-            data: T::fetch(world, 0)?,
-            _world: world,
-        }))
+    fn fetch(
+        world: &'world_borrow World,
+        _state: &'world_borrow mut (),
+    ) -> Result<Self::Item, FetchError> {
+        Ok(Some(Query::fetch(world, LockMode::Try)?))
+    }
+
+    fn entity_counts(item: &Self::Item, counts: &mut Vec<usize>) {
+        if let Some(query) = item {
+            counts.push(query.len());
+        }
     }
 }
 
@@ -54,14 +224,61 @@ pub trait FetchItem<'a> {
     fn inner(&'a mut self) -> Self::InnerItem;
 }
 
-pub trait Fetch<'world_borrow> {
+pub trait Fetch<'world_borrow, State = ()> {
     type Item: for<'a> FetchItem<'a>;
-    fn fetch(world: &'world_borrow World) -> Result<Self::Item, FetchError>;
+    fn fetch(
+        world: &'world_borrow World,
+        state: &'world_borrow mut State,
+    ) -> Result<Self::Item, FetchError>;
+
+    /// Adds how many entities each query in `item` matched to `counts`, for `SystemStats`.
+    fn entity_counts(_item: &Self::Item, _counts: &mut Vec<usize>) {}
 }
 
 pub struct Query<'world_borrow, T: QueryParameters> {
     data: <T as QueryParameterFetch<'world_borrow>>::FetchItem,
-    _world: &'world_borrow World,
+    world: &'world_borrow World,
+}
+
+impl<'world_borrow, T: QueryParameters> Query<'world_borrow, T> {
+    pub(crate) fn fetch(
+        world: &'world_borrow World,
+        lock_mode: LockMode,
+    ) -> Result<Self, FetchError> {
+        Ok(Query {
+            data: T::fetch(world, 0, lock_mode)?,
+            world,
+        })
+    }
+
+    /// Returns true if no entities match the query.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Enemy;
+    ///
+    /// let mut world = World::new();
+    /// assert!(world.query::<(&Enemy,)>().unwrap().is_empty());
+    /// world.spawn((Enemy,));
+    /// assert!(!world.query::<(&Enemy,)>().unwrap().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        !self
+            .world
+            .archetypes
+            .iter()
+            .any(|archetype| !archetype.entities.is_empty() && T::matches_archetype(archetype))
+    }
+
+    /// The number of entities that match the query.
+    pub fn len(&self) -> usize {
+        self.world
+            .archetypes
+            .iter()
+            .filter(|archetype| T::matches_archetype(archetype))
+            .map(|archetype| archetype.entities.len())
+            .sum()
+    }
 }
 
 impl<'a, 'world_borrow, T: QueryParameters> FetchItem<'a> for Option<Query<'world_borrow, T>> {
@@ -85,8 +302,109 @@ impl<'a, 'world_borrow, T: 'a> FetchItem<'a> for RwLockWriteGuard<'world_borrow,
     }
 }
 
+/// A read-only borrow of a component type's storage within one archetype.
+pub enum ReadBorrow<'world_borrow, T> {
+    Column(RwLockReadGuard<'world_borrow, Vec<T>>),
+    /// Tags have no column, so this borrows the first `len` instances of the world's tag pool instead.
+    Tag {
+        instances: RwLockReadGuard<'world_borrow, Vec<T>>,
+        len: usize,
+    },
+}
+
+impl<'world_borrow, T> Deref for ReadBorrow<'world_borrow, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        match self {
+            ReadBorrow::Column(guard) => guard,
+            ReadBorrow::Tag { instances, len } => &instances[..*len],
+        }
+    }
+}
+
+/// A mutable borrow of a component type's storage within one archetype.
+pub enum WriteBorrow<'world_borrow, T> {
+    Column(RwLockWriteGuard<'world_borrow, Vec<T>>),
+    /// Tags have no column, so this holds instances moved out of the world's tag pool instead.
+    Tag(TagsMut<'world_borrow, T>),
+}
+
+impl<'world_borrow, T> Deref for WriteBorrow<'world_borrow, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        match self {
+            WriteBorrow::Column(guard) => guard,
+            WriteBorrow::Tag(tags) => tags,
+        }
+    }
+}
+
+impl<'world_borrow, T> DerefMut for WriteBorrow<'world_borrow, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match self {
+            WriteBorrow::Column(guard) => guard,
+            WriteBorrow::Tag(tags) => tags,
+        }
+    }
+}
+
+/// Returns true if any entity has a component of type `T`.
+fn any_entity_has<T: 'static>(world: &World) -> bool {
+    let type_id = TypeId::of::<T>();
+    world
+        .archetypes
+        .iter()
+        .any(|archetype| !archetype.entities.is_empty() && archetype.contains(type_id))
+}
+
+/// Borrows `len` instances of a tag from the world's pool to read.
+fn read_tags<T: 'static>(world: &World, len: usize) -> Result<ReadBorrow<'_, T>, FetchError> {
+    world
+        .tag_pool::<T>()
+        .and_then(|tags| tags.read())
+        .map(|instances| ReadBorrow::Tag { instances, len })
+        .ok_or_else(|| FetchError::ComponentAlreadyBorrowed(ComponentAlreadyBorrowed::new::<T>()))
+}
+
+/// Moves `len` instances of a tag out of the world's pool to borrow them mutably.
+fn write_tags<T: 'static>(world: &World, len: usize) -> Result<WriteBorrow<'_, T>, FetchError> {
+    world
+        .tag_pool::<T>()
+        .and_then(|tags| tags.write(len))
+        .map(WriteBorrow::Tag)
+        .ok_or_else(|| FetchError::ComponentAlreadyBorrowed(ComponentAlreadyBorrowed::new::<T>()))
+}
+
+/// Borrows a column to read, waiting for it if `lock_mode` blocks.
+fn read_column<T: 'static>(
+    column: &RwLock<Vec<T>>,
+    lock_mode: LockMode,
+) -> Result<ReadBorrow<'_, T>, FetchError> {
+    let guard = match lock_mode {
+        LockMode::Try => column.try_read().ok(),
+        LockMode::Block => column.read().ok(),
+    };
+    guard
+        .map(ReadBorrow::Column)
+        .ok_or_else(|| FetchError::ComponentAlreadyBorrowed(ComponentAlreadyBorrowed::new::<T>()))
+}
+
+/// Borrows a column mutably, waiting for it if `lock_mode` blocks.
+fn write_column<T: 'static>(
+    column: &RwLock<Vec<T>>,
+    lock_mode: LockMode,
+) -> Result<WriteBorrow<'_, T>, FetchError> {
+    let guard = match lock_mode {
+        LockMode::Try => column.try_write().ok(),
+        LockMode::Block => column.write().ok(),
+    };
+    guard
+        .map(WriteBorrow::Column)
+        .ok_or_else(|| FetchError::ComponentAlreadyBorrowed(ComponentAlreadyBorrowed::new::<T>()))
+}
+
 pub struct Single<'world_borrow, T> {
-    borrow: RwLockReadGuard<'world_borrow, Vec<T>>,
+    borrow: ReadBorrow<'world_borrow, T>,
 }
 
 impl<'a, 'world_borrow, T: 'a> FetchItem<'a> for Single<'world_borrow, T> {
@@ -96,8 +414,15 @@ impl<'a, 'world_borrow, T: 'a> FetchItem<'a> for Single<'world_borrow, T> {
     }
 }
 
+impl<'world_borrow, T> Deref for Single<'world_borrow, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.borrow[0]
+    }
+}
+
 pub struct SingleMut<'world_borrow, T> {
-    borrow: RwLockWriteGuard<'world_borrow, Vec<T>>,
+    borrow: WriteBorrow<'world_borrow, T>,
 }
 
 impl<'a, 'world_borrow, T: 'a> FetchItem<'a> for SingleMut<'world_borrow, T> {
@@ -107,16 +432,49 @@ impl<'a, 'world_borrow, T: 'a> FetchItem<'a> for SingleMut<'world_borrow, T> {
     }
 }
 
+impl<'world_borrow, T> Deref for SingleMut<'world_borrow, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.borrow[0]
+    }
+}
+
+impl<'world_borrow, T> DerefMut for SingleMut<'world_borrow, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.borrow[0]
+    }
+}
+
 impl<'world_borrow, T: 'static> Fetch<'world_borrow> for &T {
     type Item = Single<'world_borrow, T>;
-    fn fetch(world: &'world_borrow World) -> Result<Self::Item, FetchError> {
+    fn fetch(
+        world: &'world_borrow World,
+        _state: &'world_borrow mut (),
+    ) -> Result<Self::Item, FetchError> {
+        Single::fetch(world, LockMode::Try)
+    }
+}
+
+impl<'world_borrow, T: 'static> Single<'world_borrow, T> {
+    /// Borrows the first instance of `T` found in the world.
+    pub(crate) fn fetch(
+        world: &'world_borrow World,
+        lock_mode: LockMode,
+    ) -> Result<Self, FetchError> {
+        if is_tag::<T>() && any_entity_has::<T>(world) {
+            return Ok(Single {
+                borrow: read_tags(world, 1)?,
+            });
+        }
+
         // The archetypes must be found here.
         let type_id = TypeId::of::<T>();
         for archetype in world.archetypes.iter() {
             for (i, c) in archetype.components.iter().enumerate() {
                 if c.type_id == type_id {
-                    let borrow = archetype.get(i).try_read().unwrap();
-                    return Ok(Single { borrow });
+                    return Ok(Single {
+                        borrow: read_column(archetype.get(i), lock_mode)?,
+                    });
                 }
             }
         }
@@ -129,14 +487,34 @@ impl<'world_borrow, T: 'static> Fetch<'world_borrow> for &T {
 
 impl<'world_borrow, T: 'static> Fetch<'world_borrow> for &mut T {
     type Item = SingleMut<'world_borrow, T>;
-    fn fetch(world: &'world_borrow World) -> Result<Self::Item, FetchError> {
+    fn fetch(
+        world: &'world_borrow World,
+        _state: &'world_borrow mut (),
+    ) -> Result<Self::Item, FetchError> {
+        SingleMut::fetch(world, LockMode::Try)
+    }
+}
+
+impl<'world_borrow, T: 'static> SingleMut<'world_borrow, T> {
+    /// Mutably borrows the first instance of `T` found in the world.
+    pub(crate) fn fetch(
+        world: &'world_borrow World,
+        lock_mode: LockMode,
+    ) -> Result<Self, FetchError> {
+        if is_tag::<T>() && any_entity_has::<T>(world) {
+            return Ok(SingleMut {
+                borrow: write_tags(world, 1)?,
+            });
+        }
+
         // The archetypes must be found here.
         let type_id = TypeId::of::<T>();
         for archetype in world.archetypes.iter() {
             for (i, c) in archetype.components.iter().enumerate() {
                 if c.type_id == type_id {
-                    let borrow = archetype.get(i).try_write().unwrap();
-                    return Ok(SingleMut { borrow });
+                    return Ok(SingleMut {
+                        borrow: write_column(archetype.get(i), lock_mode)?,
+                    });
                 }
             }
         }
@@ -147,11 +525,169 @@ impl<'world_borrow, T: 'static> Fetch<'world_borrow> for &mut T {
     }
 }
 
+/// A read-only borrow of one entity's component.
+/// The component's storage stays borrowed until this is dropped.
+pub struct ComponentRef<'world_borrow, T> {
+    borrow: ReadBorrow<'world_borrow, T>,
+    index: usize,
+}
+
+impl<'world_borrow, T> Deref for ComponentRef<'world_borrow, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.borrow[self.index]
+    }
+}
+
+/// A mutable borrow of one entity's component.
+/// The component's storage stays borrowed until this is dropped.
+pub struct ComponentMut<'world_borrow, T> {
+    borrow: WriteBorrow<'world_borrow, T>,
+    index: usize,
+}
+
+impl<'world_borrow, T> Deref for ComponentMut<'world_borrow, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.borrow[self.index]
+    }
+}
+
+impl<'world_borrow, T> DerefMut for ComponentMut<'world_borrow, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.borrow[self.index]
+    }
+}
+
+/// Borrows a single component of an entity whose location is already known.
+/// Implemented for `&T` and `&mut T`.
+pub trait ComponentParameter<'world_borrow> {
+    type Item;
+    fn get_component(
+        world: &'world_borrow World,
+        entity_index: EntityId,
+        location: EntityLocation,
+    ) -> Result<Self::Item, ComponentError>;
+}
+
+impl<'world_borrow, T: 'static> ComponentParameter<'world_borrow> for &T {
+    type Item = ComponentRef<'world_borrow, T>;
+    fn get_component(
+        world: &'world_borrow World,
+        entity_index: EntityId,
+        location: EntityLocation,
+    ) -> Result<Self::Item, ComponentError> {
+        let archetype = &world.archetypes[location.archetype_index as usize];
+        let type_id = TypeId::of::<T>();
+
+        if is_tag::<T>() && archetype.contains(type_id) {
+            // Every instance of a tag is the same so any of them can be borrowed.
+            return Ok(ComponentRef {
+                borrow: read_tags(world, 1).map_err(|_| {
+                    ComponentError::ComponentAlreadyBorrowed(ComponentAlreadyBorrowed::new::<T>())
+                })?,
+                index: 0,
+            });
+        }
+
+        let component_index = archetype.component_index(type_id).ok_or_else(|| {
+            ComponentError::EntityMissingComponent(EntityMissingComponent::new::<T>(entity_index))
+        })?;
+        if let Ok(read_guard) = archetype.get(component_index).try_read() {
+            Ok(ComponentRef {
+                borrow: ReadBorrow::Column(read_guard),
+                index: location.index_in_archetype as usize,
+            })
+        } else {
+            Err(ComponentError::ComponentAlreadyBorrowed(
+                ComponentAlreadyBorrowed::new::<T>(),
+            ))
+        }
+    }
+}
+
+impl<'world_borrow, T: 'static> ComponentParameter<'world_borrow> for &mut T {
+    type Item = ComponentMut<'world_borrow, T>;
+    fn get_component(
+        world: &'world_borrow World,
+        entity_index: EntityId,
+        location: EntityLocation,
+    ) -> Result<Self::Item, ComponentError> {
+        let archetype = &world.archetypes[location.archetype_index as usize];
+        let type_id = TypeId::of::<T>();
+
+        if is_tag::<T>() && archetype.contains(type_id) {
+            return Ok(ComponentMut {
+                borrow: write_tags(world, 1).map_err(|_| {
+                    ComponentError::ComponentAlreadyBorrowed(ComponentAlreadyBorrowed::new::<T>())
+                })?,
+                index: 0,
+            });
+        }
+
+        let component_index = archetype.component_index(type_id).ok_or_else(|| {
+            ComponentError::EntityMissingComponent(EntityMissingComponent::new::<T>(entity_index))
+        })?;
+        if let Ok(write_guard) = archetype.get(component_index).try_write() {
+            Ok(ComponentMut {
+                borrow: WriteBorrow::Column(write_guard),
+                index: location.index_in_archetype as usize,
+            })
+        } else {
+            Err(ComponentError::ComponentAlreadyBorrowed(
+                ComponentAlreadyBorrowed::new::<T>(),
+            ))
+        }
+    }
+}
+
+/// A tuple of `ComponentParameter`s to borrow from one entity.
+pub trait ComponentParameters<'world_borrow> {
+    type Item;
+    fn get_components(
+        world: &'world_borrow World,
+        entity_index: EntityId,
+        location: EntityLocation,
+    ) -> Result<Self::Item, ComponentError>;
+}
+
+macro_rules! component_parameters_impl {
+    ($($name: ident),*) => {
+        impl<'world_borrow, $($name: ComponentParameter<'world_borrow>,)*> ComponentParameters<'world_borrow> for ($($name,)*) {
+            type Item = ($($name::Item,)*);
+            fn get_components(
+                world: &'world_borrow World,
+                entity_index: EntityId,
+                location: EntityLocation,
+            ) -> Result<Self::Item, ComponentError> {
+                Ok(($($name::get_component(world, entity_index, location)?,)*))
+            }
+        }
+    };
+}
+
+component_parameters_impl! {A}
+component_parameters_impl! {A, B}
+component_parameters_impl! {A, B, C}
+component_parameters_impl! {A, B, C, D}
+component_parameters_impl! {A, B, C, D, E}
+component_parameters_impl! {A, B, C, D, E, F}
+component_parameters_impl! {A, B, C, D, E, F, G}
+component_parameters_impl! {A, B, C, D, E, F, G, H}
+component_parameters_impl! {A, B, C, D, E, F, G, H, I}
+component_parameters_impl! {A, B, C, D, E, F, G, H, I, J}
+component_parameters_impl! {A, B, C, D, E, F, G, H, I, J, K}
+component_parameters_impl! {A, B, C, D, E, F, G, H, I, J, K, L}
+
 // Request the data from the world for a specific lifetime.
 // This could instead be part of QueryParameter if Generic Associated Types were done.
 pub trait QueryParameterFetch<'a> {
     type FetchItem;
-    fn fetch(world: &'a World, archetype: usize) -> Result<Self::FetchItem, FetchError>;
+    fn fetch(
+        world: &'a World,
+        archetype: usize,
+        lock_mode: LockMode,
+    ) -> Result<Self::FetchItem, FetchError>;
 }
 
 #[doc(hidden)]
@@ -160,23 +696,21 @@ pub struct ReadQueryParameterFetch<T> {
 }
 
 impl<'a, T: 'static> QueryParameterFetch<'a> for ReadQueryParameterFetch<T> {
-    type FetchItem = RwLockReadGuard<'a, Vec<T>>;
-    fn fetch(world: &'a World, archetype: usize) -> Result<Self::FetchItem, FetchError> {
+    type FetchItem = ReadBorrow<'a, T>;
+    fn fetch(
+        world: &'a World,
+        archetype: usize,
+        lock_mode: LockMode,
+    ) -> Result<Self::FetchItem, FetchError> {
         let archetype = &world.archetypes[archetype];
-        let type_id = TypeId::of::<T>();
 
-        let index = archetype
-            .components
-            .iter()
-            .position(|c| c.type_id == type_id)
-            .unwrap();
-        if let Ok(read_guard) = archetype.get(index).try_read() {
-            Ok(read_guard)
-        } else {
-            Err(FetchError::ComponentAlreadyBorrowed(
-                ComponentAlreadyBorrowed::new::<T>(),
-            ))
+        if is_tag::<T>() {
+            // The pool holds at least as many tags as any one archetype has entities.
+            return read_tags(world, archetype.entities.len());
         }
+
+        let index = archetype.component_index(TypeId::of::<T>()).unwrap();
+        read_column(archetype.get(index), lock_mode)
     }
 }
 
@@ -187,23 +721,44 @@ impl<'a, T: 'static> QueryParameterFetch<'a> for ReadQueryParameterFetch<T> {
 pub trait QueryParameter {
     type QueryParameterFetch: for<'a> QueryParameterFetch<'a>;
     fn matches_archetype(archetype: &Archetype) -> bool;
+    /// Declares which components this parameter reads and writes.
+    fn access(access: &mut Access);
+    /// The component whose storage this parameter borrows, if any.
+    /// A query borrows its parameters in order of this so that blocking fetches lock in the same order.
+    fn borrowed_type_id() -> Option<TypeId> {
+        None
+    }
 }
 
 impl<T: 'static> QueryParameter for &T {
     type QueryParameterFetch = ReadQueryParameterFetch<T>;
 
+    fn access(access: &mut Access) {
+        access.read::<T>()
+    }
+
+    fn borrowed_type_id() -> Option<TypeId> {
+        Some(TypeId::of::<T>())
+    }
+
     fn matches_archetype(archetype: &Archetype) -> bool {
-        let type_id = TypeId::of::<T>();
-        archetype.components.iter().any(|c| c.type_id == type_id)
+        archetype.contains(TypeId::of::<T>())
     }
 }
 
 impl<T: 'static> QueryParameter for &mut T {
     type QueryParameterFetch = WriteQueryParameterFetch<T>;
 
+    fn access(access: &mut Access) {
+        access.write::<T>()
+    }
+
+    fn borrowed_type_id() -> Option<TypeId> {
+        Some(TypeId::of::<T>())
+    }
+
     fn matches_archetype(archetype: &Archetype) -> bool {
-        let type_id = TypeId::of::<T>();
-        archetype.components.iter().any(|c| c.type_id == type_id)
+        archetype.contains(TypeId::of::<T>())
     }
 }
 
@@ -216,17 +771,18 @@ pub struct Has<T> {
 
 impl<'world_borrow, T: 'static> QueryParameterFetch<'world_borrow> for Has<T> {
     type FetchItem = bool;
-    fn fetch(world: &'world_borrow World, archetype: usize) -> Result<Self::FetchItem, FetchError> {
+    fn fetch(
+        world: &'world_borrow World,
+        archetype: usize,
+        _lock_mode: LockMode,
+    ) -> Result<Self::FetchItem, FetchError> {
         let archetype = &world.archetypes[archetype];
-        let type_id = TypeId::of::<T>();
-
-        let contains = archetype.components.iter().any(|c| c.type_id == type_id);
-        Ok(contains)
+        Ok(archetype.contains(TypeId::of::<T>()))
     }
 }
 
 // If a boolean value is reported, just repeat its result.
-impl<'a, 'world_borrow> QueryIter<'a> for bool {
+impl<'a> QueryIter<'a> for bool {
     type Iter = std::iter::Repeat<bool>;
     fn iter(&'a mut self) -> Self::Iter {
         std::iter::repeat(*self)
@@ -236,61 +792,149 @@ impl<'a, 'world_borrow> QueryIter<'a> for bool {
 impl<T: 'static> QueryParameter for Has<T> {
     type QueryParameterFetch = Self;
 
+    // Only which archetypes have the component is checked, so nothing is borrowed.
+    fn access(_access: &mut Access) {}
+
     fn matches_archetype(_archetype: &Archetype) -> bool {
         true
     }
 }
 
+/// Filters a query to entities that have a component, without accessing that component.
+/// This is most useful with zero-sized tag components, which have no storage to borrow.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Enemy;
+/// let mut world = World::new();
+/// world.spawn((10, Enemy));
+/// world.spawn((20,));
+///
+/// let mut query = world.query::<(&i32, With<Enemy>)>().unwrap();
+/// assert_eq!(query.iter().count(), 1);
+/// ```
+pub struct With<T> {
+    phantom: std::marker::PhantomData<T>,
+}
+
+impl<'world_borrow, T: 'static> QueryParameterFetch<'world_borrow> for With<T> {
+    type FetchItem = ();
+    fn fetch(
+        _world: &'world_borrow World,
+        _archetype: usize,
+        _lock_mode: LockMode,
+    ) -> Result<Self::FetchItem, FetchError> {
+        Ok(())
+    }
+}
+
+// There is nothing to borrow so just repeat the unit value.
+impl<'a> QueryIter<'a> for () {
+    type Iter = std::iter::Repeat<()>;
+    fn iter(&'a mut self) -> Self::Iter {
+        std::iter::repeat(())
+    }
+}
+
+impl<T: 'static> QueryParameter for With<T> {
+    type QueryParameterFetch = Self;
+
+    fn access(_access: &mut Access) {}
+
+    fn matches_archetype(archetype: &Archetype) -> bool {
+        archetype.contains(TypeId::of::<T>())
+    }
+}
+
 #[doc(hidden)]
 pub struct WriteQueryParameterFetch<T> {
     phantom: std::marker::PhantomData<T>,
 }
 
 impl<'world_borrow, T: 'static> QueryParameterFetch<'world_borrow> for WriteQueryParameterFetch<T> {
-    type FetchItem = RwLockWriteGuard<'world_borrow, Vec<T>>;
-    fn fetch(world: &'world_borrow World, archetype: usize) -> Result<Self::FetchItem, FetchError> {
+    type FetchItem = WriteBorrow<'world_borrow, T>;
+    fn fetch(
+        world: &'world_borrow World,
+        archetype: usize,
+        lock_mode: LockMode,
+    ) -> Result<Self::FetchItem, FetchError> {
         let archetype = &world.archetypes[archetype];
-        let type_id = TypeId::of::<T>();
 
-        let index = archetype
-            .components
-            .iter()
-            .position(|c| c.type_id == type_id)
-            .unwrap();
-        if let Ok(write_guard) = archetype.get(index).try_write() {
-            Ok(write_guard)
-        } else {
-            Err(FetchError::ComponentAlreadyBorrowed(
-                ComponentAlreadyBorrowed::new::<T>(),
-            ))
+        if is_tag::<T>() {
+            return write_tags(world, archetype.entities.len());
         }
+
+        let index = archetype.component_index(TypeId::of::<T>()).unwrap();
+        write_column(archetype.get(index), lock_mode)
     }
 }
 
-pub trait QueryParameters: for<'a> QueryParameterFetch<'a> {}
+/// A `QueryParameter` that only reads.
+pub trait ReadOnlyQueryParameter: QueryParameter {}
+
+impl<T: 'static> ReadOnlyQueryParameter for &T {}
+impl<T: 'static> ReadOnlyQueryParameter for Has<T> {}
+impl<T: 'static> ReadOnlyQueryParameter for With<T> {}
+
+/// `QueryParameters` that only read, so they can be fetched through a `ReadOnlyWorld`.
+pub trait ReadOnlyQueryParameters: QueryParameters {}
+
+pub trait QueryParameters: for<'a> QueryParameterFetch<'a> {
+    /// Declares which components the query reads and writes.
+    fn access(access: &mut Access);
+    /// Returns true if every parameter matches the archetype.
+    fn matches_archetype(archetype: &Archetype) -> bool;
+}
 
 macro_rules! query_parameters_impl {
-    ($($name: ident),*) => {
+    ($(($name: ident, $index: tt)),*) => {
         impl<'world_borrow, $($name: QueryParameter,)*> QueryParameters
             for ($($name,)*)
-        {}
+        {
+            fn access(access: &mut Access) {
+                $(
+                    let mut parameter_access = Access::new();
+                    $name::access(&mut parameter_access);
+                    access.extend_parameter(std::any::type_name::<$name>(), &parameter_access);
+                )*
+            }
+
+            fn matches_archetype(archetype: &Archetype) -> bool {
+                $($name::matches_archetype(archetype))&&*
+            }
+        }
+
+        impl<$($name: ReadOnlyQueryParameter,)*> ReadOnlyQueryParameters for ($($name,)*) {}
 
         impl<'world_borrow, $($name: QueryParameter,)*> QueryParameterFetch<'world_borrow> for ($($name,)*) {
             #[allow(unused_parens)]
             type FetchItem = Vec<($(<$name::QueryParameterFetch as QueryParameterFetch<'world_borrow>>::FetchItem),*)>;
 
-            fn fetch(world: &'world_borrow World, _archetype: usize) -> Result<Self::FetchItem, FetchError> {
+            #[allow(non_snake_case)]
+            fn fetch(world: &'world_borrow World, _archetype: usize, lock_mode: LockMode) -> Result<Self::FetchItem, FetchError> {
                 let mut archetype_indices = Vec::new();
                 for (i, archetype) in world.archetypes.iter().enumerate() {
-                    let matches = $($name::matches_archetype(&archetype))&&*;
-                    if matches {
+                    if <Self as QueryParameters>::matches_archetype(archetype) {
                         archetype_indices.push(i);
                     }
                 }
 
+                // Archetypes are borrowed in order, and within each archetype the parameters are
+                // borrowed in order of their component's `TypeId`.
+                // Every blocking fetch then takes locks in the same order so they can't deadlock.
+                let mut order = [$(($name::borrowed_type_id(), $index)),*];
+                order.sort_unstable();
+
                 let mut result = Vec::with_capacity(archetype_indices.len());
                 for index in archetype_indices {
-                    result.push(($(<$name::QueryParameterFetch as QueryParameterFetch<'world_borrow>>::fetch(world, index)?),*));
+                    $(let mut $name = None;)*
+                    for (_, parameter) in order.iter() {
+                        match parameter {
+                            $($index => $name = Some(<$name::QueryParameterFetch as QueryParameterFetch<'world_borrow>>::fetch(world, index, lock_mode)?),)*
+                            _ => unreachable!(),
+                        }
+                    }
+                    result.push(($($name.unwrap()),*));
                 }
 
                 Ok(result)
@@ -300,17 +944,17 @@ macro_rules! query_parameters_impl {
     };
 }
 
-query_parameters_impl! {A}
-query_parameters_impl! {A, B}
-query_parameters_impl! {A, B, C}
-query_parameters_impl! {A, B, C, D}
-query_parameters_impl! {A, B, C, D, E}
-query_parameters_impl! {A, B, C, D, E, F}
-query_parameters_impl! {A, B, C, D, E, F, G}
-query_parameters_impl! {A, B, C, D, E, F, G, H}
-query_parameters_impl! {A, B, C, D, E, F, G, H, I}
-query_parameters_impl! {A, B, C, D, E, F, G, H, I, J, K}
-query_parameters_impl! {A, B, C, D, E, F, G, H, I, J, K, L}
+query_parameters_impl! {(A, 0)}
+query_parameters_impl! {(A, 0), (B, 1)}
+query_parameters_impl! {(A, 0), (B, 1), (C, 2)}
+query_parameters_impl! {(A, 0), (B, 1), (C, 2), (D, 3)}
+query_parameters_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4)}
+query_parameters_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5)}
+query_parameters_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6)}
+query_parameters_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7)}
+query_parameters_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8)}
+query_parameters_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10)}
+query_parameters_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10), (L, 11)}
 
 type QueryParameterItem<'world_borrow, Q> =
     <<Q as QueryParameter>::QueryParameterFetch as QueryParameterFetch<'world_borrow>>::FetchItem;
@@ -320,14 +964,14 @@ pub trait QueryIter<'a> {
     fn iter(&'a mut self) -> Self::Iter;
 }
 
-impl<'a, 'world_borrow, T: 'static> QueryIter<'a> for RwLockReadGuard<'world_borrow, Vec<T>> {
+impl<'a, 'world_borrow, T: 'static> QueryIter<'a> for ReadBorrow<'world_borrow, T> {
     type Iter = std::slice::Iter<'a, T>;
     fn iter(&'a mut self) -> Self::Iter {
         <[T]>::iter(self)
     }
 }
 
-impl<'a, 'world_borrow, T: 'static> QueryIter<'a> for RwLockWriteGuard<'world_borrow, Vec<T>> {
+impl<'a, 'world_borrow, T: 'static> QueryIter<'a> for WriteBorrow<'world_borrow, T> {
     type Iter = std::slice::IterMut<'a, T>;
     fn iter(&'a mut self) -> Self::Iter {
         <[T]>::iter_mut(self)
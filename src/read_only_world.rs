@@ -0,0 +1,92 @@
+//! A view of the world that systems can take to read anything in it.
+//!
+//! Some `World` methods write through a shared reference, such as `get_single_mut`,
+//! so systems take a `ReadOnlyWorld`, which only has the methods that read, to run alongside
+//! other systems. A system that needs the whole `World` is an exclusive system instead.
+
+use crate::{
+    Access, ComponentError, ComponentRef, Entity, Fetch, FetchError, FetchItem, NoSuchEntity,
+    Query, ReadOnlyQueryParameters, Single, SystemParameter, World,
+};
+use std::any::TypeId;
+
+/// A system parameter that can read anything in the world,
+/// so it conflicts with any parameter that writes.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Name(String);
+///
+/// fn debug_overlay(world: ReadOnlyWorld, name: &Name) {
+///     let has_names = !world.query::<(&Name,)>().unwrap().is_empty();
+///     println!("{} {}", name.0, has_names);
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn((Name("Player".to_string()),));
+/// debug_overlay.run(&world).unwrap();
+/// ```
+///
+/// Queries through it can only read:
+/// ```compile_fail
+/// # use kudo::*;
+/// fn heal(world: ReadOnlyWorld) {
+///     world.query::<(&mut i32,)>();
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct ReadOnlyWorld<'world_borrow> {
+    world: &'world_borrow World,
+}
+
+impl<'world_borrow> ReadOnlyWorld<'world_borrow> {
+    /// Gets read-only access to a single component on an `Entity`, like `World::get`.
+    pub fn get<T: 'static>(
+        &self,
+        entity: Entity,
+    ) -> Result<ComponentRef<'world_borrow, T>, ComponentError> {
+        self.world.get(entity)
+    }
+
+    /// Gets the first instance of a component found, like `World::get_single`.
+    pub fn get_single<T: 'static>(&self) -> Result<Single<'world_borrow, T>, FetchError> {
+        self.world.get_single()
+    }
+
+    /// Gets a query that only reads, like `World::query`.
+    pub fn query<T: ReadOnlyQueryParameters>(&self) -> Result<Query<'world_borrow, T>, FetchError> {
+        self.world.query()
+    }
+
+    /// The types of all an entity's components, sorted.
+    pub fn component_type_ids(&self, entity: Entity) -> Result<Vec<TypeId>, NoSuchEntity> {
+        Ok(self.world.entity(entity)?.component_type_ids())
+    }
+}
+
+impl<'a> SystemParameter for ReadOnlyWorld<'a> {
+    type State = ();
+    type Fetch = ReadOnlyWorldFetch;
+    fn access(access: &mut Access) {
+        access.read_world()
+    }
+}
+
+pub struct ReadOnlyWorldFetch;
+
+impl<'world_borrow> Fetch<'world_borrow> for ReadOnlyWorldFetch {
+    type Item = ReadOnlyWorld<'world_borrow>;
+    fn fetch(
+        world: &'world_borrow World,
+        _state: &'world_borrow mut (),
+    ) -> Result<Self::Item, FetchError> {
+        Ok(ReadOnlyWorld { world })
+    }
+}
+
+impl<'a, 'world_borrow> FetchItem<'a> for ReadOnlyWorld<'world_borrow> {
+    type InnerItem = ReadOnlyWorld<'world_borrow>;
+    fn inner(&'a mut self) -> Self::InnerItem {
+        *self
+    }
+}
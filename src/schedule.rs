@@ -0,0 +1,718 @@
+//! A `Schedule` runs a collection of systems each time `Schedule::run` is called.
+//!
+//! Systems are first sorted so that every system runs after the systems it is ordered after,
+//! either with labels or with chains. Ties are broken by the order systems were added in.
+//!
+//! Then systems are grouped into batches using the `Access` declared by their parameters.
+//! No two systems in a batch conflict or are ordered relative to each other, so every system
+//! in a batch runs at the same time on its own thread.
+//! Exclusive systems, which take `&mut World`, conflict with everything and so run in a batch alone.
+
+use crate::{
+    BoxedCondition, BoxedSystem, Clock, IntoCondition, IntoSystem, ScheduleStats, SystemError,
+    Time, World,
+};
+use std::any::TypeId;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::time::Instant;
+
+/// Identifies a group of systems for ordering.
+/// Labels are either strings or types.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Physics;
+/// let by_name: SystemLabel = "physics".into();
+/// let by_type = SystemLabel::of::<Physics>();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemLabel {
+    Name(&'static str),
+    Type(TypeId, &'static str),
+}
+
+impl SystemLabel {
+    /// A label identified by a type.
+    pub fn of<T: 'static>() -> Self {
+        SystemLabel::Type(TypeId::of::<T>(), std::any::type_name::<T>())
+    }
+}
+
+impl From<&'static str> for SystemLabel {
+    fn from(name: &'static str) -> Self {
+        SystemLabel::Name(name)
+    }
+}
+
+impl std::fmt::Display for SystemLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SystemLabel::Name(name) => write!(f, "\"{}\"", name),
+            SystemLabel::Type(_, name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// An error that stops a `Schedule` from running.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// fn a() {}
+/// fn b() {}
+/// fn c() {}
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(a.label("a").after("c"));
+/// schedule.add_system(b.label("b").after("a"));
+/// schedule.add_system(c.label("c").after("b"));
+///
+/// let error = schedule.run(&mut World::new()).unwrap_err();
+/// let message = error.to_string();
+/// assert!(message.starts_with("Systems are ordered in a cycle: "));
+/// match error {
+///     ScheduleError::Cycle(systems) => {
+///         let mut names: Vec<_> = systems
+///             .iter()
+///             .map(|system| system.rsplit("::").next().unwrap())
+///             .collect();
+///         names.sort();
+///         assert_eq!(names, ["a", "b", "c"]);
+///         // The cycle ends where it started.
+///         assert!(message.ends_with(&format!(" -> {}", systems[0])));
+///     }
+///     _ => panic!("expected a cycle"),
+/// }
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(a.after("physics"));
+/// let error = schedule.run(&mut World::new()).unwrap_err();
+/// assert!(matches!(error, ScheduleError::UnknownLabel { .. }));
+/// assert!(error
+///     .to_string()
+///     .ends_with("a] is ordered relative to the label \"physics\" but no system has that label"));
+/// ```
+#[derive(Debug)]
+pub enum ScheduleError {
+    /// Systems are ordered before and after each other in a loop.
+    /// The names of the systems in the loop are listed in order.
+    Cycle(Vec<&'static str>),
+    /// A system is ordered relative to a label no system has.
+    UnknownLabel {
+        system: &'static str,
+        label: SystemLabel,
+    },
+    /// A system failed and the error handler chose to abort.
+    System {
+        system: &'static str,
+        error: SystemError,
+    },
+    /// A system accesses non-`Send` resources but the schedule was run on a thread
+    /// other than the one that created the world.
+    NonSendWrongThread { system: &'static str },
+}
+
+impl std::fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleError::Cycle(systems) => {
+                write!(f, "Systems are ordered in a cycle: ")?;
+                for system in systems {
+                    write!(f, "{} -> ", system)?;
+                }
+                write!(f, "{}", systems[0])
+            }
+            ScheduleError::UnknownLabel { system, label } => write!(
+                f,
+                "[{}] is ordered relative to the label {} but no system has that label",
+                system, label
+            ),
+            ScheduleError::System { system, error } => write!(f, "[{}] failed: {}", system, error),
+            ScheduleError::NonSendWrongThread { system } => write!(
+                f,
+                "[{}] accesses non-Send resources so the schedule must run on the thread that created the world",
+                system
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+/// What a `Schedule` does after a system fails.
+/// Actions are ordered by severity and the most severe action for a batch is taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorAction {
+    /// Keep running the schedule as if the system succeeded.
+    Continue,
+    /// Finish the current batch then stop this run without returning an error.
+    Skip,
+    /// Finish the current batch then stop this run and return the error.
+    Abort,
+}
+
+type ErrorHandler = Box<dyn FnMut(&'static str, &SystemError) -> ErrorAction + Send + Sync>;
+
+/// A collection of systems that are run together, in parallel where possible.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Position(f32);
+/// struct Velocity(f32);
+///
+/// fn apply_velocity(mut query: Query<(&mut Position, &Velocity)>) {
+///     for (position, velocity) in query.iter() {
+///         position.0 += velocity.0;
+///     }
+/// }
+///
+/// fn print_velocities(mut query: Query<(&Velocity,)>) {
+///     for velocity in query.iter() {
+///         println!("Velocity: {:?}", velocity.0);
+///     }
+/// }
+///
+/// fn print_positions(mut query: Query<(&Position,)>) {
+///     for position in query.iter() {
+///         println!("Position: {:?}", position.0);
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn((Position(0.), Velocity(1.)));
+///
+/// let mut schedule = Schedule::new();
+/// schedule
+///     .add_system(print_positions.label("print").after("physics"))
+///     .add_system(apply_velocity.label("physics"))
+///     .add_system(print_velocities);
+/// schedule.build().unwrap();
+///
+/// // `print_velocities` only reads so it runs alongside `apply_velocity`.
+/// // `print_positions` must wait until `apply_velocity` is done.
+/// assert_eq!(schedule.batch_count(), 2);
+/// schedule.run(&mut world).unwrap();
+/// ```
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<BoxedSystem>,
+    /// Pairs of systems where the first must run before the second, from chains.
+    chained: Vec<(usize, usize)>,
+    /// The batch each system runs in, as of the last build.
+    batch_of_system: Vec<usize>,
+    batch_count: usize,
+    built: bool,
+    error_handler: Option<ErrorHandler>,
+    conditions: Vec<BoxedCondition>,
+    clock: Option<Box<dyn Clock>>,
+    /// The clock's time at the start of the last run.
+    last_tick: Option<std::time::Duration>,
+    /// Statistics for the schedule's runs, if enabled. Systems keep their own.
+    stats: Option<ScheduleStats>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a system to the schedule.
+    /// Unless ordered otherwise it runs after any previously added systems whose access
+    /// conflicts with its own.
+    pub fn add_system<P>(&mut self, system: impl IntoSystem<P>) -> &mut Self {
+        self.push_system(system.system());
+        self.built = false;
+        self
+    }
+
+    fn push_system(&mut self, mut system: BoxedSystem) {
+        if self.stats.is_some() {
+            system.enable_stats();
+        }
+        self.systems.push(system);
+    }
+
+    /// Adds systems that each run after the one before it.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// fn read_input() {}
+    /// fn physics() {}
+    /// fn render() {}
+    ///
+    /// let mut schedule = Schedule::new();
+    /// schedule.add_chain(vec![read_input.system(), physics.system(), render.system()]);
+    /// schedule.build().unwrap();
+    /// assert_eq!(schedule.batch_count(), 3);
+    /// ```
+    pub fn add_chain(&mut self, systems: Vec<BoxedSystem>) -> &mut Self {
+        let first = self.systems.len();
+        for (i, system) in systems.into_iter().enumerate() {
+            if i > 0 {
+                self.chained.push((first + i - 1, first + i));
+            }
+            self.push_system(system);
+        }
+        self.built = false;
+        self
+    }
+
+    /// Updates the world's `Time` resource from the clock at the start of every run.
+    /// The first run's delta is the time since the clock started.
+    pub fn set_clock(&mut self, clock: impl Clock) -> &mut Self {
+        self.clock = Some(Box::new(clock));
+        self.last_tick = None;
+        self
+    }
+
+    /// Only runs the schedule when the condition returns true.
+    /// If a schedule has multiple conditions they must all return true.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct EditorMode(bool);
+    ///
+    /// fn in_game(editor_mode: &EditorMode) -> bool {
+    ///     !editor_mode.0
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((EditorMode(true),));
+    ///
+    /// let mut schedule = Schedule::new();
+    /// schedule.run_if(in_game);
+    /// schedule.run(&mut world).unwrap();
+    /// ```
+    pub fn run_if<P>(&mut self, condition: impl IntoCondition<P>) -> &mut Self {
+        self.conditions.push(condition.condition());
+        self
+    }
+
+    /// Decides what happens when a system fails, given the system's name and its error.
+    /// Without a handler every error aborts the run.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// fn fails() -> Result<(), String> {
+    ///     Err("Something went wrong".to_string())
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// let mut schedule = Schedule::new();
+    /// schedule.add_system(fails).set_error_handler(|system, error| {
+    ///     eprintln!("[{}] failed: {}", system, error);
+    ///     ErrorAction::Continue
+    /// });
+    /// assert!(schedule.run(&mut world).is_ok());
+    /// ```
+    pub fn set_error_handler(
+        &mut self,
+        handler: impl FnMut(&'static str, &SystemError) -> ErrorAction + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.error_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Starts recording how long the schedule and each of its systems take,
+    /// discarding any previous stats.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Position(f32);
+    ///
+    /// fn movement(mut query: Query<(&mut Position,)>) {
+    ///     for position in query.iter() {
+    ///         position.0 += 1.;
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((Position(0.),));
+    ///
+    /// let mut schedule = Schedule::new();
+    /// schedule.add_system(movement).enable_stats();
+    /// schedule.run(&mut world).unwrap();
+    ///
+    /// let stats = schedule.stats().unwrap();
+    /// let movement = &stats.systems()[0];
+    /// assert_eq!(movement.runs(), 1);
+    /// assert_eq!(movement.entities(), &[1]);
+    /// println!("{:?} fetching, {:?} running", movement.fetch_time(), movement.run_time());
+    /// # let _ = stats.chrome_trace();
+    /// ```
+    pub fn enable_stats(&mut self) -> &mut Self {
+        self.stats = Some(ScheduleStats::new());
+        for system in self.systems.iter_mut() {
+            system.enable_stats();
+        }
+        self
+    }
+
+    /// Statistics for every run since `enable_stats` was called.
+    pub fn stats(&self) -> Option<ScheduleStats> {
+        let mut stats = self.stats.clone()?;
+        stats.systems = self
+            .systems
+            .iter()
+            .filter_map(|system| system.stats().cloned())
+            .collect();
+        Some(stats)
+    }
+
+    /// Sorts the systems and groups them into batches that can run in parallel.
+    /// This is done automatically by `run` if systems were added since the last build,
+    /// but calling it directly reports ordering errors up front.
+    pub fn build(&mut self) -> Result<(), ScheduleError> {
+        let count = self.systems.len();
+        let predecessors = self.predecessors()?;
+
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); count];
+        for (i, system_predecessors) in predecessors.iter().enumerate() {
+            for p in system_predecessors.iter() {
+                successors[*p].push(i);
+            }
+        }
+
+        // Sort the systems, always picking the earliest added system that's ready to keep things predictable.
+        let mut remaining_predecessors: Vec<usize> = predecessors.iter().map(|p| p.len()).collect();
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..count)
+            .filter(|i| remaining_predecessors[*i] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(count);
+        while let Some(Reverse(i)) = ready.pop() {
+            order.push(i);
+            for s in successors[i].iter() {
+                remaining_predecessors[*s] -= 1;
+                if remaining_predecessors[*s] == 0 {
+                    ready.push(Reverse(*s));
+                }
+            }
+        }
+
+        if order.len() < count {
+            return Err(ScheduleError::Cycle(
+                self.find_cycle(&predecessors, &remaining_predecessors),
+            ));
+        }
+
+        // Each system goes in the batch after the last batch with a system it must run after
+        // or a system it conflicts with that was sorted before it.
+        let mut batch_of_system = vec![0; count];
+        let mut batch_count = 0;
+        for (position, i) in order.iter().enumerate() {
+            let after_predecessors = predecessors[*i].iter().map(|p| batch_of_system[*p] + 1);
+            let after_conflicts = order[..position]
+                .iter()
+                .filter(|j| {
+                    self.systems[**j]
+                        .access()
+                        .conflicts_with(self.systems[*i].access())
+                })
+                .map(|j| batch_of_system[*j] + 1);
+            let batch = after_predecessors.chain(after_conflicts).max().unwrap_or(0);
+
+            batch_of_system[*i] = batch;
+            batch_count = batch_count.max(batch + 1);
+        }
+
+        self.batch_of_system = batch_of_system;
+        self.batch_count = batch_count;
+        self.built = true;
+        Ok(())
+    }
+
+    /// For each system, the systems that must run before it.
+    fn predecessors(&self) -> Result<Vec<Vec<usize>>, ScheduleError> {
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); self.systems.len()];
+        for (i, system) in self.systems.iter().enumerate() {
+            for label in system.before.iter() {
+                for other in self.systems_with_label(system.name(), *label)? {
+                    predecessors[other].push(i);
+                }
+            }
+            for label in system.after.iter() {
+                for other in self.systems_with_label(system.name(), *label)? {
+                    predecessors[i].push(other);
+                }
+            }
+        }
+        for (before, after) in self.chained.iter() {
+            predecessors[*after].push(*before);
+        }
+        Ok(predecessors)
+    }
+
+    fn systems_with_label(
+        &self,
+        system: &'static str,
+        label: SystemLabel,
+    ) -> Result<Vec<usize>, ScheduleError> {
+        let systems: Vec<usize> = (0..self.systems.len())
+            .filter(|i| self.systems[*i].labels.contains(&label))
+            .collect();
+        if systems.is_empty() {
+            Err(ScheduleError::UnknownLabel { system, label })
+        } else {
+            Ok(systems)
+        }
+    }
+
+    /// Finds the names of systems in a cycle among the systems that could not be sorted.
+    fn find_cycle(
+        &self,
+        predecessors: &[Vec<usize>],
+        remaining_predecessors: &[usize],
+    ) -> Vec<&'static str> {
+        let unsorted = |i: usize| remaining_predecessors[i] > 0;
+
+        // Every unsorted system has an unsorted predecessor, so walking backwards must loop.
+        let mut path = vec![(0..predecessors.len()).find(|i| unsorted(*i)).unwrap()];
+        loop {
+            let current = *path.last().unwrap();
+            let predecessor = *predecessors[current]
+                .iter()
+                .find(|p| unsorted(**p))
+                .unwrap();
+            if let Some(start) = path.iter().position(|i| *i == predecessor) {
+                return path[start..]
+                    .iter()
+                    .rev()
+                    .map(|i| self.systems[*i].name())
+                    .collect();
+            }
+            path.push(predecessor);
+        }
+    }
+
+    /// Describes the schedule as a Graphviz graph, building it first if needed.
+    ///
+    /// Each batch is drawn as a box around its systems, and exclusive systems are drawn as rectangles.
+    /// Solid arrows point from a system to the systems ordered after it.
+    /// Dashed red lines join systems that can't run at the same time because their access conflicts,
+    /// labelled with the components they conflict over.
+    /// Exclusive systems conflict with every system so their conflicts aren't drawn.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Position(f32);
+    ///
+    /// fn movement(position: &mut Position) {}
+    /// fn render(position: &Position) {}
+    ///
+    /// let mut schedule = Schedule::new();
+    /// schedule.add_system(movement).add_system(render);
+    /// let dot = schedule.to_dot().unwrap();
+    /// assert!(dot.starts_with("digraph schedule {"));
+    /// // Saved to a file, `dot -Tsvg schedule.dot -o schedule.svg` draws the graph.
+    /// println!("{}", dot);
+    /// ```
+    pub fn to_dot(&mut self) -> Result<String, ScheduleError> {
+        if !self.built {
+            self.build()?;
+        }
+        let predecessors = self.predecessors()?;
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let mut dot = String::from("digraph schedule {\n");
+        for batch in 0..self.batch_count {
+            dot += &format!(
+                "    subgraph cluster_{} {{\n        label={};\n",
+                batch,
+                quote(&format!("Batch {}", batch))
+            );
+            for (i, system) in self.systems.iter().enumerate() {
+                if self.batch_of_system[i] == batch {
+                    let shape = if system.is_exclusive() {
+                        "box"
+                    } else {
+                        "ellipse"
+                    };
+                    dot += &format!(
+                        "        system_{} [label={}, shape={}];\n",
+                        i,
+                        quote(system.name()),
+                        shape
+                    );
+                }
+            }
+            dot += "    }\n";
+        }
+
+        for (i, system_predecessors) in predecessors.iter().enumerate() {
+            let mut system_predecessors = system_predecessors.clone();
+            system_predecessors.sort_unstable();
+            system_predecessors.dedup();
+            for p in system_predecessors {
+                dot += &format!("    system_{} -> system_{};\n", p, i);
+            }
+        }
+
+        for (i, first) in self.systems.iter().enumerate() {
+            for (j, second) in self.systems.iter().enumerate().skip(i + 1) {
+                if first.is_exclusive() || second.is_exclusive() {
+                    continue;
+                }
+                let components = first.access().conflicting_components(second.access());
+                if !components.is_empty() {
+                    dot += &format!(
+                        "    system_{} -> system_{} [dir=none, style=dashed, color=red, constraint=false, label={}];\n",
+                        i,
+                        j,
+                        quote(&components.join(", "))
+                    );
+                }
+            }
+        }
+        dot += "}\n";
+        Ok(dot)
+    }
+
+    /// How many batches of systems run one after another, as of the last build.
+    pub fn batch_count(&self) -> usize {
+        self.batch_count
+    }
+
+    /// The names of the systems in each batch, as of the last build.
+    pub fn batches(&self) -> Vec<Vec<&'static str>> {
+        let mut batches = vec![Vec::new(); self.batch_count];
+        for (system, batch) in self.systems.iter().zip(self.batch_of_system.iter()) {
+            batches[*batch].push(system.name());
+        }
+        batches
+    }
+
+    /// Runs every system once, building the schedule first if needed.
+    /// All systems in a batch are run even if one fails, then the error handler decides
+    /// whether later batches run.
+    /// Nothing is run if any of the schedule's conditions return false or fail.
+    pub fn run(&mut self, world: &mut World) -> Result<(), ScheduleError> {
+        let start = Instant::now();
+        let result = self.run_once(world);
+        if let Some(stats) = &mut self.stats {
+            stats.runs += 1;
+            stats.run_time += start.elapsed();
+        }
+        result
+    }
+
+    fn run_once(&mut self, world: &mut World) -> Result<(), ScheduleError> {
+        if !self.built {
+            self.build()?;
+        }
+
+        if std::thread::current().id() != world.thread() {
+            if let Some(system) = self
+                .systems
+                .iter()
+                .find(|system| system.access().is_non_send())
+            {
+                return Err(ScheduleError::NonSendWrongThread {
+                    system: system.name(),
+                });
+            }
+        }
+
+        if let Some(clock) = &self.clock {
+            let now = clock.elapsed();
+            let delta = now - self.last_tick.unwrap_or_default();
+            self.last_tick = Some(now);
+
+            let mut time = world
+                .get_single::<Time>()
+                .map(|time| *time)
+                .unwrap_or_default();
+            time.advance(delta);
+            world.insert_resource(time);
+        }
+
+        for condition in self.conditions.iter_mut() {
+            match condition.run(world) {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(e) => {
+                    return Err(ScheduleError::System {
+                        system: condition.name(),
+                        error: e.into(),
+                    })
+                }
+            }
+        }
+
+        for batch in 0..self.batch_count {
+            let results = self.run_batch(batch, world);
+
+            let mut action = ErrorAction::Continue;
+            for (system, result) in results {
+                if let Err(error) = result {
+                    let system_action = match &mut self.error_handler {
+                        Some(handler) => handler(system, &error),
+                        None => ErrorAction::Abort,
+                    };
+                    if system_action == ErrorAction::Abort {
+                        return Err(ScheduleError::System { system, error });
+                    }
+                    action = action.max(system_action);
+                }
+            }
+            if action == ErrorAction::Skip {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the systems in a batch, returning each system's name and result.
+    fn run_batch(
+        &mut self,
+        batch: usize,
+        world: &mut World,
+    ) -> Vec<(&'static str, Result<(), SystemError>)> {
+        if let Some((system, _)) = self
+            .systems
+            .iter_mut()
+            .zip(self.batch_of_system.iter())
+            .find(|(system, b)| **b == batch && system.is_exclusive())
+        {
+            return vec![(system.name(), system.run_exclusive(world))];
+        }
+
+        let world = &*world;
+        // Systems that access non-`Send` resources run on the calling thread,
+        // or if there are none the first system in the batch does.
+        let (mut local, mut spawned): (Vec<_>, Vec<_>) = self
+            .systems
+            .iter_mut()
+            .zip(self.batch_of_system.iter())
+            .filter(|(_, b)| **b == batch)
+            .map(|(system, _)| system)
+            .partition(|system| system.access().is_non_send());
+        if local.is_empty() {
+            local.push(spawned.remove(0));
+        }
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = spawned
+                .into_iter()
+                .map(|system| {
+                    let name = system.name();
+                    (name, scope.spawn(move || system.run(world)))
+                })
+                .collect();
+
+            let mut results: Vec<_> = local
+                .into_iter()
+                .map(|system| (system.name(), system.run(world)))
+                .collect();
+            for (name, handle) in handles {
+                results.push((
+                    name,
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+                ));
+            }
+            results
+        })
+    }
+}
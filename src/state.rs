@@ -0,0 +1,243 @@
+//! A state machine, such as a game moving between its menu and gameplay, run as a schedule stage.
+//!
+//! The current state is stored in the world as the `State<S>` resource.
+//! Systems request a transition by setting the `NextState<S>` resource,
+//! and the transition is applied the next time the `StateMachine` stage runs.
+
+use crate::{
+    AccessConflict, BoxedCondition, BoxedSystem, Component, IntoCondition, IntoSystem, Query,
+    QueryIter, Schedule, ScheduleError, SystemError, World,
+};
+
+/// Values that can be used as states.
+pub trait States: Clone + PartialEq + std::fmt::Debug + Component {}
+
+impl<S: Clone + PartialEq + std::fmt::Debug + Component> States for S {}
+
+/// A resource with the current state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct State<S: States>(S);
+
+impl<S: States> State<S> {
+    pub fn get(&self) -> &S {
+        &self.0
+    }
+}
+
+/// A resource used to request a transition to another state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NextState<S: States>(Option<S>);
+
+impl<S: States> NextState<S> {
+    /// Requests a transition, replacing any previous request.
+    pub fn set(&mut self, state: S) {
+        self.0 = Some(state);
+    }
+
+    /// The requested state, if any.
+    pub fn get(&self) -> Option<&S> {
+        self.0.as_ref()
+    }
+}
+
+impl<S: States> Default for NextState<S> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+/// A run condition that is true while the state is `state`.
+/// It's false until the `StateMachine` has run for the first time and added the state to the world.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum GameState {
+///     Menu,
+///     Playing,
+/// }
+///
+/// fn movement() {}
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(movement.run_if(in_state(GameState::Playing)));
+/// ```
+pub fn in_state<S: States>(state: S) -> BoxedCondition {
+    (move |mut current: Query<(&State<S>,)>| {
+        current
+            .iter()
+            .next()
+            .is_some_and(|current| current.0 == state)
+    })
+    .condition()
+}
+
+/// A stage that applies requested state transitions then runs the current state's systems.
+///
+/// Each time the stage runs:
+/// 1. If the world has no `State<S>` yet the initial state is entered,
+///    and `NextState<S>` is added unless it already was.
+/// 2. If a transition was requested the current state's `on_exit` systems run,
+///    then the state changes, then the new state's `on_enter` systems run.
+/// 3. The current state's `on_update` systems run.
+///
+/// A `StateMachine` is added to a schedule like a system, and runs alone like an exclusive system.
+/// To request a transition before the stage first runs, insert a `NextState<S>` resource.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum GameState {
+///     Menu,
+///     Playing,
+/// }
+///
+/// struct Level;
+///
+/// fn start_game(next_state: &mut NextState<GameState>) {
+///     next_state.set(GameState::Playing);
+/// }
+///
+/// fn load_level(world: &mut World) {
+///     world.spawn((Level,));
+/// }
+///
+/// let mut states = StateMachine::new(GameState::Menu);
+/// states
+///     .on_update(GameState::Menu, start_game)
+///     .on_enter(GameState::Playing, load_level.exclusive_system());
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(states);
+///
+/// let mut world = World::new();
+/// schedule.run(&mut world).unwrap();
+/// assert_eq!(world.get_single::<State<GameState>>().unwrap().get(), &GameState::Menu);
+///
+/// schedule.run(&mut world).unwrap();
+/// assert_eq!(world.get_single::<State<GameState>>().unwrap().get(), &GameState::Playing);
+/// assert!(!world.query::<(&Level,)>().unwrap().is_empty());
+/// ```
+///
+/// A transition requested before the first run is applied straight after entering the initial state.
+/// ```
+/// # use kudo::*;
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum GameState {
+///     Menu,
+///     Playing,
+/// }
+///
+/// struct Ticks(u32);
+///
+/// fn tick(ticks: &mut Ticks) {
+///     ticks.0 += 1;
+/// }
+///
+/// let mut schedule = Schedule::new();
+/// schedule.add_system(tick.run_if(in_state(GameState::Menu)));
+/// schedule.add_system(StateMachine::new(GameState::Menu));
+///
+/// let mut world = World::new();
+/// world.insert_resource(Ticks(0));
+/// let mut next_state = NextState::default();
+/// next_state.set(GameState::Playing);
+/// world.insert_resource(next_state);
+///
+/// schedule.run(&mut world).unwrap();
+/// assert_eq!(world.get_single::<State<GameState>>().unwrap().get(), &GameState::Playing);
+/// assert_eq!(world.get_single::<Ticks>().unwrap().0, 0);
+/// ```
+pub struct StateMachine<S: States> {
+    initial: S,
+    on_enter: Vec<(S, Schedule)>,
+    on_exit: Vec<(S, Schedule)>,
+    on_update: Vec<(S, Schedule)>,
+}
+
+/// Finds the schedule for a state, adding one if there isn't one yet.
+fn schedule_for<S: States>(schedules: &mut Vec<(S, Schedule)>, state: S) -> &mut Schedule {
+    let index = match schedules.iter().position(|(s, _)| *s == state) {
+        Some(index) => index,
+        None => {
+            schedules.push((state, Schedule::new()));
+            schedules.len() - 1
+        }
+    };
+    &mut schedules[index].1
+}
+
+/// Runs the schedule for a state if there is one.
+fn run_schedule_for<S: States>(
+    schedules: &mut [(S, Schedule)],
+    state: &S,
+    world: &mut World,
+) -> Result<(), ScheduleError> {
+    match schedules.iter_mut().find(|(s, _)| s == state) {
+        Some((_, schedule)) => schedule.run(world),
+        None => Ok(()),
+    }
+}
+
+impl<S: States> StateMachine<S> {
+    pub fn new(initial: S) -> Self {
+        Self {
+            initial,
+            on_enter: Vec::new(),
+            on_exit: Vec::new(),
+            on_update: Vec::new(),
+        }
+    }
+
+    /// Adds a system that runs when `state` is entered.
+    pub fn on_enter<P>(&mut self, state: S, system: impl IntoSystem<P>) -> &mut Self {
+        schedule_for(&mut self.on_enter, state).add_system(system);
+        self
+    }
+
+    /// Adds a system that runs when `state` is exited.
+    pub fn on_exit<P>(&mut self, state: S, system: impl IntoSystem<P>) -> &mut Self {
+        schedule_for(&mut self.on_exit, state).add_system(system);
+        self
+    }
+
+    /// Adds a system that runs every time the stage runs while in `state`.
+    pub fn on_update<P>(&mut self, state: S, system: impl IntoSystem<P>) -> &mut Self {
+        schedule_for(&mut self.on_update, state).add_system(system);
+        self
+    }
+
+    /// Applies a requested transition, if any, then runs the current state's `on_update` systems.
+    pub fn run(&mut self, world: &mut World) -> Result<(), ScheduleError> {
+        if world.get_single::<State<S>>().is_err() {
+            world.insert_resource(State(self.initial.clone()));
+            if world.get_single::<NextState<S>>().is_err() {
+                world.insert_resource(NextState::<S>::default());
+            }
+            run_schedule_for(&mut self.on_enter, &self.initial, world)?;
+        }
+
+        let current = world.get_single::<State<S>>().unwrap().0.clone();
+        let next = world.get_single_mut::<NextState<S>>().unwrap().0.take();
+        let current = match next {
+            Some(next) if next != current => {
+                run_schedule_for(&mut self.on_exit, &current, world)?;
+                world.get_single_mut::<State<S>>().unwrap().0 = next.clone();
+                run_schedule_for(&mut self.on_enter, &next, world)?;
+                next
+            }
+            _ => current,
+        };
+
+        run_schedule_for(&mut self.on_update, &current, world)
+    }
+}
+
+impl<S: States> IntoSystem<StateMachine<S>> for StateMachine<S> {
+    fn try_system(mut self) -> Result<BoxedSystem, AccessConflict> {
+        Ok(BoxedSystem::exclusive(
+            std::any::type_name::<StateMachine<S>>(),
+            move |world| self.run(world).map_err(|e| SystemError::System(e.into())),
+        ))
+    }
+}
@@ -0,0 +1,192 @@
+//! Opt-in timing statistics for systems and schedules.
+//!
+//! When stats are enabled with `Schedule::enable_stats` each system measures how long fetching
+//! its parameters takes, which includes waiting on locks, separately from how long it runs.
+//! Every run is also recorded as a span so a whole schedule can be viewed as a Chrome trace,
+//! by loading the file written by `ScheduleStats::write_chrome_trace` in `chrome://tracing`.
+//! Only the most recent `MAX_SPANS` runs of each system are kept, so a schedule can run with stats
+//! enabled indefinitely without its memory growing.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+/// Measurements from a single run of a system.
+#[derive(Debug, Clone, Default)]
+pub struct SystemProfile {
+    /// Time spent fetching parameters, including acquiring locks.
+    pub fetch_time: Duration,
+    /// Time spent running the system's function.
+    pub run_time: Duration,
+    /// The number of entities matched by each of the system's queries, in parameter order.
+    pub entities: Vec<usize>,
+}
+
+/// How many of each system's most recent runs are kept for `ScheduleStats::chrome_trace`.
+pub const MAX_SPANS: usize = 1024;
+
+/// A single run of a system.
+#[derive(Debug, Clone)]
+struct Span {
+    start: Instant,
+    duration: Duration,
+    thread: ThreadId,
+}
+
+/// Statistics for one system across every run since stats were enabled.
+#[derive(Debug, Clone)]
+pub struct SystemStats {
+    name: &'static str,
+    runs: u32,
+    fetch_time: Duration,
+    run_time: Duration,
+    entities: Vec<usize>,
+    /// The most recent runs, oldest first.
+    spans: VecDeque<Span>,
+}
+
+impl SystemStats {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            runs: 0,
+            fetch_time: Duration::ZERO,
+            run_time: Duration::ZERO,
+            entities: Vec::new(),
+            spans: VecDeque::new(),
+        }
+    }
+
+    /// Records a run that started at `start`.
+    pub(crate) fn record(&mut self, start: Instant, profile: SystemProfile) {
+        self.runs += 1;
+        self.fetch_time += profile.fetch_time;
+        self.run_time += profile.run_time;
+        self.entities = profile.entities;
+        if self.spans.len() == MAX_SPANS {
+            self.spans.pop_front();
+        }
+        self.spans.push_back(Span {
+            start,
+            duration: start.elapsed(),
+            thread: std::thread::current().id(),
+        });
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// How many times the system ran. Runs skipped by a run condition aren't counted.
+    pub fn runs(&self) -> u32 {
+        self.runs
+    }
+
+    /// Total time spent fetching parameters, including acquiring locks.
+    pub fn fetch_time(&self) -> Duration {
+        self.fetch_time
+    }
+
+    /// Total time spent running the system's function.
+    pub fn run_time(&self) -> Duration {
+        self.run_time
+    }
+
+    /// The number of entities matched by each of the system's queries on its last run.
+    pub fn entities(&self) -> &[usize] {
+        &self.entities
+    }
+}
+
+/// Statistics for a schedule and each of its systems, from `Schedule::stats`.
+#[derive(Debug, Clone)]
+pub struct ScheduleStats {
+    pub(crate) started: Instant,
+    pub(crate) runs: u32,
+    pub(crate) run_time: Duration,
+    pub(crate) systems: Vec<SystemStats>,
+}
+
+impl ScheduleStats {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            runs: 0,
+            run_time: Duration::ZERO,
+            systems: Vec::new(),
+        }
+    }
+
+    /// How many times the schedule ran.
+    pub fn runs(&self) -> u32 {
+        self.runs
+    }
+
+    /// Total time spent running the schedule.
+    pub fn run_time(&self) -> Duration {
+        self.run_time
+    }
+
+    /// Statistics for each system, in the order they were added.
+    pub fn systems(&self) -> &[SystemStats] {
+        &self.systems
+    }
+
+    /// The systems' runs in Chrome's trace event format.
+    /// Each thread a system ran on is shown as its own row.
+    /// Only the most recent `MAX_SPANS` runs of each system are included.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// fn idle() {}
+    ///
+    /// let mut world = World::new();
+    /// let mut schedule = Schedule::new();
+    /// schedule.add_system(idle).enable_stats();
+    /// for _ in 0..MAX_SPANS + 10 {
+    ///     schedule.run(&mut world).unwrap();
+    /// }
+    ///
+    /// let stats = schedule.stats().unwrap();
+    /// assert_eq!(stats.systems()[0].runs() as usize, MAX_SPANS + 10);
+    /// assert_eq!(stats.chrome_trace().matches("\"ph\":\"X\"").count(), MAX_SPANS);
+    /// ```
+    pub fn chrome_trace(&self) -> String {
+        let mut threads: Vec<ThreadId> = Vec::new();
+        let mut events = Vec::new();
+        for system in self.systems.iter() {
+            for span in system.spans.iter() {
+                let thread = match threads.iter().position(|t| *t == span.thread) {
+                    Some(thread) => thread,
+                    None => {
+                        threads.push(span.thread);
+                        threads.len() - 1
+                    }
+                };
+                let mut event = String::new();
+                write!(
+                    event,
+                    "{{\"name\":\"{}\",\"cat\":\"system\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":0,\"tid\":{}}}",
+                    escape(system.name),
+                    span.start.saturating_duration_since(self.started).as_micros(),
+                    span.duration.as_micros(),
+                    thread
+                )
+                .unwrap();
+                events.push(event);
+            }
+        }
+        format!("{{\"traceEvents\":[{}]}}", events.join(","))
+    }
+
+    /// Writes `chrome_trace` to a file.
+    pub fn write_chrome_trace(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.chrome_trace())
+    }
+}
+
+/// Escapes a string for use in JSON.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
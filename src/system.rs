@@ -1,6 +1,12 @@
 use crate::SystemParameter;
 
-use super::{Fetch, FetchError, FetchItem, World};
+use super::{
+    Access, AccessConflict, Entity, ExclusiveSystem, Fetch, FetchError, FetchItem, SystemError,
+    SystemLabel, SystemProfile, SystemStats, World,
+};
+use std::convert::Infallible;
+use std::error::Error;
+use std::time::Instant;
 
 /// A function that can be run as system by pulling in queries from the world.
 /// # Example
@@ -25,17 +31,727 @@ use super::{Fetch, FetchError, FetchItem, World};
 ///
 /// my_system.run(&world).unwrap();
 /// ```
+///
+/// Systems can return a `Result` to report their own errors.
+/// ```
+/// # use kudo::*;
+/// struct Health(u32);
+///
+/// fn total_health(mut query: Query<(&Health,)>) -> Result<u32, String> {
+///     let total: u32 = query.iter().map(|health| health.0).sum();
+///     if total == 0 {
+///         return Err("Everyone is dead".to_string());
+///     }
+///     Ok(total)
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn((Health(10),));
+/// assert_eq!(total_health.run(&world).unwrap(), 10);
+/// ```
 pub trait System<P> {
-    fn run(self, world: &World) -> Result<(), FetchError>;
+    /// The value passed to the system's `In` parameter, or `()` if it has none.
+    type Input;
+    /// The value returned by the system's function.
+    type Output;
+    /// The state of each of the system's parameters, kept between runs.
+    type State: Default + Send + Sync + 'static;
+
+    /// Runs the system with state from previous runs, returning exactly what the function returned.
+    fn run_with_state(
+        &mut self,
+        world: &World,
+        input: Self::Input,
+        state: &mut Self::State,
+    ) -> Result<Self::Output, FetchError>;
+
+    /// Runs the system like `run_with_state`, measuring how long it takes.
+    /// By default all the time is counted as running the system.
+    fn run_profiled(
+        &mut self,
+        world: &World,
+        input: Self::Input,
+        state: &mut Self::State,
+        profile: &mut SystemProfile,
+    ) -> Result<Self::Output, FetchError> {
+        let start = Instant::now();
+        let output = self.run_with_state(world, input, state);
+        profile.run_time += start.elapsed();
+        output
+    }
+
+    /// Runs the system once with fresh state.
+    /// If the system returns a `Result` its error is combined with any error fetching its parameters.
+    #[allow(clippy::type_complexity)]
+    fn run(
+        self,
+        world: &World,
+    ) -> Result<
+        <Self::Output as SystemOutput>::Ok,
+        SystemError<<Self::Output as SystemOutput>::Error>,
+    >
+    where
+        Self: Sized + System<P, Input = ()>,
+        Self::Output: SystemOutput,
+    {
+        self.run_with(world, ())
+    }
+
+    /// Runs the system once with fresh state, passing `input` to its `In` parameter.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Position(f32);
+    ///
+    /// fn movement(In(delta): In<f32>, mut query: Query<(&mut Position,)>) {
+    ///     for position in query.iter() {
+    ///         position.0 += delta;
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((Position(0.),));
+    /// movement.run_with(&world, 0.5).unwrap();
+    /// ```
+    #[allow(clippy::type_complexity)]
+    fn run_with(
+        mut self,
+        world: &World,
+        input: Self::Input,
+    ) -> Result<
+        <Self::Output as SystemOutput>::Ok,
+        SystemError<<Self::Output as SystemOutput>::Error>,
+    >
+    where
+        Self: Sized,
+        Self::Output: SystemOutput,
+    {
+        self.run_with_state(world, input, &mut Self::State::default())?
+            .into_result()
+            .map_err(SystemError::System)
+    }
+
+    /// Boxes the system as a function that takes an input each time it's run.
+    /// Unlike `run_with` the system's state is kept between runs.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Packet(Vec<u8>);
+    /// struct Received(usize);
+    ///
+    /// fn receive(In(packet): In<Packet>, received: &mut Received) -> usize {
+    ///     received.0 += packet.0.len();
+    ///     received.0
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((Received(0),));
+    ///
+    /// let mut receive: BoxedInputSystem<Packet, usize> = receive.boxed();
+    /// receive(&world, Packet(vec![1, 2])).unwrap();
+    /// assert_eq!(receive(&world, Packet(vec![3])).unwrap(), 3);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the system's parameters conflict with each other, like `IntoSystem::system`.
+    fn boxed(self) -> BoxedInputSystem<Self::Input, <Self::Output as SystemOutput>::Ok>
+    where
+        Self: Sized + Send + Sync + 'static,
+        Self::Output: SystemOutput,
+        <Self::Output as SystemOutput>::Error: Into<Box<dyn Error + Send + Sync>>,
+    {
+        self.try_boxed()
+            .unwrap_or_else(|conflict| panic!("{}", conflict))
+    }
+
+    /// Boxes the system like `boxed`, or returns an error if its parameters conflict with each other.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Health(u32);
+    ///
+    /// fn heal(In(amount): In<u32>, mut query: Query<(&mut Health,)>, lowest: &Health) {}
+    ///
+    /// let conflict = heal.try_boxed().err().unwrap();
+    /// assert!(conflict.to_string().contains("heal] has conflicting parameters"));
+    /// ```
+    fn try_boxed(
+        self,
+    ) -> Result<BoxedInputSystem<Self::Input, <Self::Output as SystemOutput>::Ok>, AccessConflict>
+    where
+        Self: Sized + Send + Sync + 'static,
+        Self::Output: SystemOutput,
+        <Self::Output as SystemOutput>::Error: Into<Box<dyn Error + Send + Sync>>,
+    {
+        if let Some(mut conflict) = Self::conflict() {
+            conflict.system.get_or_insert(std::any::type_name::<Self>());
+            return Err(conflict);
+        }
+        let mut system = self;
+        let mut state = Self::State::default();
+        Ok(Box::new(move |world, input| {
+            system
+                .run_with_state(world, input, &mut state)?
+                .into_result()
+                .map_err(|e| SystemError::System(e.into()))
+        }))
+    }
+
+    /// The components this system reads and writes, collected from its `SystemParameter`s.
+    fn access() -> Access;
+
+    /// Finds two of the system's parameters that conflict with each other.
+    fn conflict() -> Option<AccessConflict> {
+        Self::access().conflict()
+    }
+
+    /// Creates a system that runs this system then passes its output to `next`'s `In` parameter.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Health(u32);
+    ///
+    /// fn count_wounded(mut query: Query<(&Health,)>) -> usize {
+    ///     query.iter().filter(|health| health.0 < 10).count()
+    /// }
+    ///
+    /// fn report(In(wounded): In<usize>) {
+    ///     println!("{} wounded", wounded);
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((Health(5),));
+    /// count_wounded.pipe(report).run(&world).unwrap();
+    /// ```
+    fn pipe<B, PB>(self, next: B) -> PipeSystem<Self, B>
+    where
+        Self: Sized,
+        B: System<PB, Input = Self::Output>,
+    {
+        PipeSystem {
+            first: self,
+            second: next,
+        }
+    }
 }
 
-pub trait IntoSystem<P> {
-    fn system(self) -> Box<dyn FnMut(&World) -> Result<(), FetchError> + Send + Sync>;
+/// A system parameter that receives the output of the previous system in a pipe,
+/// or the input passed to `System::run_with`. It must be the first parameter.
+pub struct In<T>(pub T);
+
+/// A system boxed by `System::boxed` that takes a `T` each time it's run and returns an `R`.
+pub type BoxedInputSystem<T, R = ()> =
+    Box<dyn FnMut(&World, T) -> Result<R, SystemError> + Send + Sync>;
+
+/// Two systems where the output of the first is the input of the second.
+/// Created with `System::pipe`.
+pub struct PipeSystem<A, B> {
+    first: A,
+    second: B,
+}
+
+#[doc(hidden)]
+pub struct PipeMarker<PA, PB>(std::marker::PhantomData<(PA, PB)>);
+
+impl<PA, PB, A: System<PA>, B: System<PB, Input = A::Output>> System<PipeMarker<PA, PB>>
+    for PipeSystem<A, B>
+{
+    type Input = A::Input;
+    type Output = B::Output;
+    type State = (A::State, B::State);
+
+    fn run_with_state(
+        &mut self,
+        world: &World,
+        input: A::Input,
+        state: &mut Self::State,
+    ) -> Result<B::Output, FetchError> {
+        let output = self.first.run_with_state(world, input, &mut state.0)?;
+        self.second.run_with_state(world, output, &mut state.1)
+    }
+
+    fn run_profiled(
+        &mut self,
+        world: &World,
+        input: A::Input,
+        state: &mut Self::State,
+        profile: &mut SystemProfile,
+    ) -> Result<B::Output, FetchError> {
+        let output = self
+            .first
+            .run_profiled(world, input, &mut state.0, profile)?;
+        self.second
+            .run_profiled(world, output, &mut state.1, profile)
+    }
+
+    fn access() -> Access {
+        let mut access = A::access();
+        access.extend(&B::access());
+        access
+    }
+
+    fn conflict() -> Option<AccessConflict> {
+        // The systems run one after the other so they can't conflict with each other.
+        let named = |system: &'static str| {
+            move |mut conflict: AccessConflict| {
+                conflict.system.get_or_insert(system);
+                conflict
+            }
+        };
+        A::conflict()
+            .map(named(std::any::type_name::<A>()))
+            .or_else(|| B::conflict().map(named(std::any::type_name::<B>())))
+    }
+}
+
+/// A value returned by a system, which may be an error.
+/// This is implemented for `Result`, and for `()`, `Entity`, `Vec`, `Option`, tuples
+/// and common plain values, which never fail.
+///
+/// Other types can be returned by implementing this for them.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Health(u32);
+///
+/// struct Summary {
+///     alive: usize,
+/// }
+///
+/// impl SystemOutput for Summary {
+///     type Ok = Summary;
+///     type Error = std::convert::Infallible;
+///     fn into_result(self) -> Result<Summary, std::convert::Infallible> {
+///         Ok(self)
+///     }
+/// }
+///
+/// fn summarize(mut query: Query<(&Health,)>) -> (Summary, Vec<u32>) {
+///     let healths: Vec<u32> = query.iter().map(|health| health.0).collect();
+///     let alive = healths.iter().filter(|health| **health > 0).count();
+///     (Summary { alive }, healths)
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn((Health(10),));
+/// world.spawn((Health(0),));
+/// let (summary, healths) = summarize.run(&world).unwrap();
+/// assert_eq!(summary.alive, 1);
+/// assert_eq!(healths.len(), 2);
+/// ```
+pub trait SystemOutput {
+    type Ok;
+    type Error;
+    fn into_result(self) -> Result<Self::Ok, Self::Error>;
+}
+
+impl<T, E> SystemOutput for Result<T, E> {
+    type Ok = T;
+    type Error = E;
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+}
+
+macro_rules! infallible_output_impl {
+    ($($t: ty),*) => {
+        $(
+            impl SystemOutput for $t {
+                type Ok = $t;
+                type Error = Infallible;
+                fn into_result(self) -> Result<$t, Infallible> {
+                    Ok(self)
+                }
+            }
+        )*
+    };
+}
+
+infallible_output_impl! {(), bool, char, String, Entity, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64}
+
+impl<T> SystemOutput for Vec<T> {
+    type Ok = Vec<T>;
+    type Error = Infallible;
+    fn into_result(self) -> Result<Vec<T>, Infallible> {
+        Ok(self)
+    }
+}
+
+// `None` is a value like any other rather than a failure.
+impl<T> SystemOutput for Option<T> {
+    type Ok = Option<T>;
+    type Error = Infallible;
+    fn into_result(self) -> Result<Option<T>, Infallible> {
+        Ok(self)
+    }
+}
+
+macro_rules! tuple_output_impl {
+    ($($name: ident),*) => {
+        impl<$($name,)*> SystemOutput for ($($name,)*) {
+            type Ok = ($($name,)*);
+            type Error = Infallible;
+            fn into_result(self) -> Result<Self::Ok, Infallible> {
+                Ok(self)
+            }
+        }
+    };
+}
+
+tuple_output_impl! {A}
+tuple_output_impl! {A, B}
+tuple_output_impl! {A, B, C}
+tuple_output_impl! {A, B, C, D}
+tuple_output_impl! {A, B, C, D, E}
+tuple_output_impl! {A, B, C, D, E, F}
+tuple_output_impl! {A, B, C, D, E, F, G}
+tuple_output_impl! {A, B, C, D, E, F, G, H}
+tuple_output_impl! {A, B, C, D, E, F, G, H, I}
+tuple_output_impl! {A, B, C, D, E, F, G, H, I, J}
+tuple_output_impl! {A, B, C, D, E, F, G, H, I, J, K}
+tuple_output_impl! {A, B, C, D, E, F, G, H, I, J, K, L}
+
+type BoxedRun =
+    Box<dyn FnMut(&World, Option<&mut SystemProfile>) -> Result<(), SystemError> + Send + Sync>;
+type BoxedExclusiveRun = Box<dyn FnMut(&mut World) -> Result<(), SystemError> + Send + Sync>;
+
+enum SystemRun {
+    Shared(BoxedRun),
+    /// Exclusive systems take the whole `World` so they can spawn and despawn entities.
+    Exclusive(BoxedExclusiveRun),
+}
+
+type BoxedConditionRun = Box<dyn FnMut(&World) -> Result<bool, FetchError> + Send + Sync>;
+
+/// A read-only system that returns `bool`, boxed to decide whether a system or schedule runs.
+pub struct BoxedCondition {
+    name: &'static str,
+    access: Access,
+    run: BoxedConditionRun,
+}
+
+impl BoxedCondition {
+    /// The name of the condition's type, which for a function is the function's path.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The components this condition reads.
+    pub fn access(&self) -> &Access {
+        &self.access
+    }
+
+    pub fn run(&mut self, world: &World) -> Result<bool, FetchError> {
+        (self.run)(world)
+    }
+}
+
+pub trait IntoCondition<P> {
+    /// Boxes the condition.
+    /// # Panics
+    /// Panics if the condition writes to any component.
+    /// Conditions are run before the systems they guard, so writes could hide changes from them.
+    fn condition(self) -> BoxedCondition;
+}
+
+impl<P, S: System<P, Input = (), Output = bool> + Send + Sync + 'static> IntoCondition<P> for S {
+    fn condition(self) -> BoxedCondition {
+        let name = std::any::type_name::<S>();
+        let access = S::access();
+        if let Some(write) = access.writes().next() {
+            panic!(
+                "[{}] can't be a run condition because `{}` writes [{}]",
+                name, write.parameter, write.type_name
+            );
+        }
+
+        let mut system = self;
+        let mut state = S::State::default();
+        BoxedCondition {
+            name,
+            access,
+            run: Box::new(move |world| system.run_with_state(world, (), &mut state)),
+        }
+    }
+}
+
+impl IntoCondition<BoxedCondition> for BoxedCondition {
+    fn condition(self) -> BoxedCondition {
+        self
+    }
+}
+
+/// A system that has been boxed so it can be stored alongside systems of other types.
+/// It remembers which components it accesses so it can be scheduled.
+pub struct BoxedSystem {
+    name: &'static str,
+    access: Access,
+    run: SystemRun,
+    pub(crate) labels: Vec<SystemLabel>,
+    pub(crate) before: Vec<SystemLabel>,
+    pub(crate) after: Vec<SystemLabel>,
+    conditions: Vec<BoxedCondition>,
+    stats: Option<SystemStats>,
+}
+
+impl BoxedSystem {
+    pub(crate) fn exclusive(
+        name: &'static str,
+        run: impl FnMut(&mut World) -> Result<(), SystemError> + Send + Sync + 'static,
+    ) -> Self {
+        let mut access = Access::new();
+        access.write_world();
+        BoxedSystem {
+            name,
+            access,
+            run: SystemRun::Exclusive(Box::new(run)),
+            labels: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+            conditions: Vec::new(),
+            stats: None,
+        }
+    }
+
+    /// Labels the system so other systems can be ordered relative to it.
+    /// A system can have multiple labels and multiple systems can share a label.
+    pub fn label(mut self, label: impl Into<SystemLabel>) -> Self {
+        self.labels.push(label.into());
+        self
+    }
+
+    /// Runs this system before all systems with the label.
+    pub fn before(mut self, label: impl Into<SystemLabel>) -> Self {
+        self.before.push(label.into());
+        self
+    }
+
+    /// Runs this system after all systems with the label.
+    pub fn after(mut self, label: impl Into<SystemLabel>) -> Self {
+        self.after.push(label.into());
+        self
+    }
+
+    /// Only runs this system when the condition returns true.
+    /// If a system has multiple conditions they must all return true.
+    /// The system's parameters aren't fetched when it's skipped.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Paused;
+    /// struct Position(f32);
+    ///
+    /// fn not_paused(query: Query<(&Paused,)>) -> bool {
+    ///     query.is_empty()
+    /// }
+    ///
+    /// fn movement(mut query: Query<(&mut Position,)>) {
+    ///     for position in query.iter() {
+    ///         position.0 += 1.0;
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((Position(0.),));
+    /// world.spawn((Paused,));
+    /// movement.run_if(not_paused).run(&mut world).unwrap();
+    /// let mut query = world.query::<(&Position,)>().unwrap();
+    /// assert_eq!(query.iter().next().unwrap().0, 0.);
+    /// ```
+    pub fn run_if<P>(mut self, condition: impl IntoCondition<P>) -> Self {
+        let condition = condition.condition();
+        // The condition is run as part of this system so it is scheduled with its access.
+        self.access.extend(condition.access());
+        self.conditions.push(condition);
+        self
+    }
+
+    /// The name of the system's type, which for a function is the function's path.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The components this system reads and writes.
+    pub fn access(&self) -> &Access {
+        &self.access
+    }
+
+    /// Returns true if the system takes `&mut World`.
+    /// Exclusive systems run alone, after every system before them and before every system after.
+    pub fn is_exclusive(&self) -> bool {
+        matches!(self.run, SystemRun::Exclusive(_))
+    }
+
+    /// Starts recording how long each run of the system takes, discarding any previous stats.
+    pub fn enable_stats(&mut self) {
+        self.stats = Some(SystemStats::new(self.name));
+    }
+
+    /// Statistics for each run since `enable_stats` was called.
+    pub fn stats(&self) -> Option<&SystemStats> {
+        self.stats.as_ref()
+    }
+
+    /// Runs every condition, returning false as soon as one does.
+    fn should_run(&mut self, world: &World) -> Result<bool, FetchError> {
+        for condition in self.conditions.iter_mut() {
+            if !condition.run(world)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Runs the system with exclusive access to the world. Both kinds of systems can be run this way.
+    pub fn run_exclusive(&mut self, world: &mut World) -> Result<(), SystemError> {
+        if !self.should_run(world)? {
+            return Ok(());
+        }
+        match &mut self.run {
+            SystemRun::Shared(run) => run_recorded(run, &mut self.stats, world),
+            SystemRun::Exclusive(run) => {
+                let start = Instant::now();
+                let result = run(world);
+                if let Some(stats) = &mut self.stats {
+                    let profile = SystemProfile {
+                        run_time: start.elapsed(),
+                        ..SystemProfile::default()
+                    };
+                    stats.record(start, profile);
+                }
+                result
+            }
+        }
+    }
+
+    /// Runs a system that is not exclusive with a shared borrow of the world,
+    /// so systems can be run on several threads at once.
+    /// An exclusive system returns an error instead, and must be run with `run_exclusive`.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// use std::sync::Arc;
+    ///
+    /// struct Score(u32);
+    ///
+    /// fn read_score(score: &Score) {
+    ///     assert_eq!(score.0, 7);
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((Score(7),));
+    /// let world = Arc::new(world);
+    ///
+    /// let threads: Vec<_> = (0..2)
+    ///     .map(|_| {
+    ///         let world = world.clone();
+    ///         std::thread::spawn(move || read_score.system().run(&world).unwrap())
+    ///     })
+    ///     .collect();
+    /// for thread in threads {
+    ///     thread.join().unwrap();
+    /// }
+    ///
+    /// fn clear(world: &mut World) {}
+    /// let error = clear.exclusive_system().run(&world).unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     SystemError::Fetch(FetchError::ExclusiveSystem(_))
+    /// ));
+    /// ```
+    pub fn run(&mut self, world: &World) -> Result<(), SystemError> {
+        if self.is_exclusive() {
+            return Err(SystemError::Fetch(FetchError::ExclusiveSystem(
+                ExclusiveSystem::new(self.name),
+            )));
+        }
+        if !self.should_run(world)? {
+            return Ok(());
+        }
+        match &mut self.run {
+            SystemRun::Shared(run) => run_recorded(run, &mut self.stats, world),
+            SystemRun::Exclusive(_) => unreachable!(),
+        }
+    }
+}
+
+/// Runs a shared system, recording the run if stats are enabled.
+fn run_recorded(
+    run: &mut BoxedRun,
+    stats: &mut Option<SystemStats>,
+    world: &World,
+) -> Result<(), SystemError> {
+    match stats {
+        Some(stats) => {
+            let start = Instant::now();
+            let mut profile = SystemProfile::default();
+            let result = run(world, Some(&mut profile));
+            stats.record(start, profile);
+            result
+        }
+        None => run(world, None),
+    }
+}
+
+pub trait IntoSystem<P>: Sized {
+    /// Boxes the system, or returns an error if its parameters conflict with each other.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Health(u32);
+    ///
+    /// fn heal(mut query: Query<(&mut Health,)>, lowest: &Health) {}
+    ///
+    /// let conflict = match heal.try_system() {
+    ///     Err(conflict) => conflict,
+    ///     Ok(_) => panic!("`heal` reads and writes `Health`"),
+    /// };
+    /// let message = conflict.to_string();
+    /// assert!(message.contains("heal] has conflicting parameters"));
+    /// assert!(message.contains("Health,)>` writes ["));
+    /// assert!(message.ends_with("Health` reads it"));
+    /// ```
+    fn try_system(self) -> Result<BoxedSystem, AccessConflict>;
+
+    /// Boxes the system.
+    /// # Panics
+    /// Panics if the system's parameters conflict with each other,
+    /// for example `Query<(&mut A,)>` and `&A`, because such a system could never run.
+    fn system(self) -> BoxedSystem {
+        self.try_system()
+            .unwrap_or_else(|conflict| panic!("{}", conflict))
+    }
+
+    /// Labels the system so other systems can be ordered relative to it.
+    fn label(self, label: impl Into<SystemLabel>) -> BoxedSystem {
+        self.system().label(label)
+    }
+
+    /// Runs this system before all systems with the label.
+    fn before(self, label: impl Into<SystemLabel>) -> BoxedSystem {
+        self.system().before(label)
+    }
+
+    /// Runs this system after all systems with the label.
+    fn after(self, label: impl Into<SystemLabel>) -> BoxedSystem {
+        self.system().after(label)
+    }
+
+    /// Only runs this system when the condition returns true.
+    fn run_if<PC>(self, condition: impl IntoCondition<PC>) -> BoxedSystem {
+        self.system().run_if(condition)
+    }
+}
+
+impl IntoSystem<BoxedSystem> for BoxedSystem {
+    fn try_system(self) -> Result<BoxedSystem, AccessConflict> {
+        Ok(self)
+    }
 }
 
 pub trait OuterSystem {
     type Input;
-    fn run<'world_borrow>(self, world: &'world_borrow World) -> Result<(), FetchError>;
+    fn run(self, world: &World) -> Result<(), FetchError>;
 }
 
 // A SystemParameter specifies how its data is fetched and represented with an associated
@@ -43,32 +759,183 @@ pub trait OuterSystem {
 // 'Fetch' has an Item that can be borrow to access its 'InnerItem'.
 // This allows the Fetch item to contain data that must be dropped *after*
 // the system executes.
-type InnerItem<'a, 'b, A> =
-    <<<A as SystemParameter>::Fetch as Fetch<'a>>::Item as FetchItem<'b>>::InnerItem;
+type InnerItem<'a, 'b, A> = <<<A as SystemParameter>::Fetch as Fetch<
+    'a,
+    <A as SystemParameter>::State,
+>>::Item as FetchItem<'b>>::InnerItem;
+
+/// Boxes a system's result, discarding its value.
+fn box_output<O: SystemOutput>(output: O) -> Result<(), SystemError>
+where
+    O::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    output
+        .into_result()
+        .map(|_| ())
+        .map_err(|e| SystemError::System(e.into()))
+}
+
+impl<P, S: System<P, Input = ()> + Sync + Send + 'static> IntoSystem<P> for S
+where
+    S::Output: SystemOutput,
+    <S::Output as SystemOutput>::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    fn try_system(self) -> Result<BoxedSystem, AccessConflict> {
+        let name = std::any::type_name::<S>();
+        if let Some(mut conflict) = S::conflict() {
+            conflict.system.get_or_insert(name);
+            return Err(conflict);
+        }
+        let access = S::access();
+
+        Ok(BoxedSystem {
+            name,
+            access,
+            run: {
+                let mut system = self;
+                let mut state = S::State::default();
+                SystemRun::Shared(Box::new(move |world, profile| {
+                    let output = match profile {
+                        Some(profile) => system.run_profiled(world, (), &mut state, profile)?,
+                        None => system.run_with_state(world, (), &mut state)?,
+                    };
+                    box_output(output)
+                }))
+            },
+            labels: Vec::new(),
+            before: Vec::new(),
+            after: Vec::new(),
+            conditions: Vec::new(),
+            stats: None,
+        })
+    }
+}
+
+/// Functions that take `&mut World` can be boxed as exclusive systems.
+/// They can't be passed directly where an `IntoSystem` is expected because a closure's
+/// parameters would be ambiguous, so `exclusive_system` must be called first.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Enemy;
+///
+/// fn load_level(world: &mut World) {
+///     world.spawn((Enemy,));
+/// }
+///
+/// let mut world = World::new();
+/// let mut system = load_level.exclusive_system();
+/// assert!(system.is_exclusive());
+/// system.run_exclusive(&mut world).unwrap();
+/// ```
+pub trait IntoExclusiveSystem<Out> {
+    fn exclusive_system(self) -> BoxedSystem;
+}
 
-impl<P, S: System<P> + Sync + Send + 'static + Copy> IntoSystem<P> for S {
-    fn system(self) -> Box<dyn FnMut(&World) -> Result<(), FetchError> + Send + Sync> {
-        Box::new(move |world| self.run(world))
+impl<F, Out> IntoExclusiveSystem<Out> for F
+where
+    F: FnMut(&mut World) -> Out + Send + Sync + 'static,
+    Out: SystemOutput,
+    Out::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    fn exclusive_system(self) -> BoxedSystem {
+        let mut system = self;
+        BoxedSystem::exclusive(std::any::type_name::<F>(), move |world| {
+            box_output(system(world))
+        })
     }
 }
 
 macro_rules! system_impl {
     ($($name: ident),*) => {
-        impl<FUNC, $($name: SystemParameter),*> System<($($name,)*)> for FUNC
+        impl<FUNC, Out, $($name: SystemParameter),*> System<($($name,)*)> for FUNC
         where
-            FUNC: FnMut($($name,)*) + for<'a, 'b> FnMut($(InnerItem<'a, 'b, $name>,)*),
+            FUNC: FnMut($($name,)*) -> Out + for<'a, 'b> FnMut($(InnerItem<'a, 'b, $name>,)*) -> Out,
         {
+            type Input = ();
+            type Output = Out;
+            type State = ($($name::State,)*);
+
             #[allow(non_snake_case)]
             #[allow(unused_variables)]
-            fn run<'world_borrow>(mut self, world: &'world_borrow World) -> Result<(), FetchError> {
-                $(let mut $name = $name::Fetch::fetch(world)?;)*
-                self($($name.inner(),)*);
-                Ok(())
+            fn run_with_state(&mut self, world: &World, _input: (), state: &mut Self::State) -> Result<Out, FetchError> {
+                let ($($name,)*) = state;
+                $(let mut $name = $name::Fetch::fetch(world, $name)?;)*
+                Ok(self($($name.inner(),)*))
+            }
+
+            #[allow(non_snake_case)]
+            #[allow(unused_variables)]
+            fn run_profiled(&mut self, world: &World, _input: (), state: &mut Self::State, profile: &mut SystemProfile) -> Result<Out, FetchError> {
+                let start = Instant::now();
+                let ($($name,)*) = state;
+                $(let mut $name = $name::Fetch::fetch(world, $name)?;)*
+                $($name::Fetch::entity_counts(&$name, &mut profile.entities);)*
+                profile.fetch_time += start.elapsed();
+
+                let start = Instant::now();
+                let output = self($($name.inner(),)*);
+                profile.run_time += start.elapsed();
+                Ok(output)
+            }
+
+            fn access() -> Access {
+                parameters_access!($($name),*)
+            }
+        }
+
+        // Systems whose first parameter is `In` take an input when run.
+        impl<FUNC, Input, Out, $($name: SystemParameter),*> System<(In<Input>, $($name,)*)> for FUNC
+        where
+            FUNC: FnMut(In<Input>, $($name,)*) -> Out + for<'a, 'b> FnMut(In<Input>, $(InnerItem<'a, 'b, $name>,)*) -> Out,
+        {
+            type Input = Input;
+            type Output = Out;
+            type State = ($($name::State,)*);
+
+            #[allow(non_snake_case)]
+            #[allow(unused_variables)]
+            fn run_with_state(&mut self, world: &World, input: Input, state: &mut Self::State) -> Result<Out, FetchError> {
+                let ($($name,)*) = state;
+                $(let mut $name = $name::Fetch::fetch(world, $name)?;)*
+                Ok(self(In(input), $($name.inner(),)*))
+            }
+
+            #[allow(non_snake_case)]
+            #[allow(unused_variables)]
+            fn run_profiled(&mut self, world: &World, input: Input, state: &mut Self::State, profile: &mut SystemProfile) -> Result<Out, FetchError> {
+                let start = Instant::now();
+                let ($($name,)*) = state;
+                $(let mut $name = $name::Fetch::fetch(world, $name)?;)*
+                $($name::Fetch::entity_counts(&$name, &mut profile.entities);)*
+                profile.fetch_time += start.elapsed();
+
+                let start = Instant::now();
+                let output = self(In(input), $($name.inner(),)*);
+                profile.run_time += start.elapsed();
+                Ok(output)
+            }
+
+            fn access() -> Access {
+                parameters_access!($($name),*)
             }
         }
     };
 }
 
+macro_rules! parameters_access {
+    ($($name: ident),*) => {{
+        #[allow(unused_mut)]
+        let mut access = Access::new();
+        $(
+            let mut parameter_access = Access::new();
+            $name::access(&mut parameter_access);
+            access.extend_parameter(std::any::type_name::<$name>(), &parameter_access);
+        )*
+        access
+    }};
+}
+
 system_impl! {}
 system_impl! {A}
 system_impl! {A, B}
@@ -0,0 +1,161 @@
+//! Declares structs that group several system parameters into one.
+//!
+//! A `SystemParameter` is fetched in two steps (see `query.rs`): its `Fetch` borrows from the world
+//! and the resulting `FetchItem` is then borrowed to produce the value passed to the system.
+//! A grouped struct needs both steps for each of its fields, which `system_parameter!` writes.
+
+/// Declares a struct whose fields are system parameters, so it can be used as one parameter.
+///
+/// The struct must have two lifetimes. The first is how long the world is borrowed for
+/// and is used by `Query` and `Local`. The second is a shorter borrow of the fetched data
+/// and is used by `&T`, `&mut T`, `EventReader`, `EventWriter` and grouped parameters.
+/// Components in a `Query` are written with `'static` since they only describe what to fetch.
+///
+/// The struct's fields are fetched in the order they are declared, and its access is checked
+/// along with the rest of the system's parameters. A `pub` struct needs its fields' types to be public.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Position(f32);
+/// struct Velocity(f32);
+/// struct Gravity(f32);
+///
+/// system_parameter! {
+///     struct Physics<'w, 's> {
+///         pub bodies: Query<'w, (&'static mut Position, &'static mut Velocity)>,
+///         pub gravity: &'s Gravity,
+///     }
+/// }
+///
+/// fn step(mut physics: Physics) {
+///     for (position, velocity) in physics.bodies.iter() {
+///         velocity.0 -= physics.gravity.0;
+///         position.0 += velocity.0;
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn((Position(0.), Velocity(0.)));
+/// world.spawn((Gravity(1.),));
+/// step.run(&world).unwrap();
+/// ```
+///
+/// A struct can group just queries, or just resources, and leave a lifetime unused.
+/// ```
+/// # use kudo::*;
+/// struct Player;
+/// struct Enemy;
+/// struct Health(u32);
+///
+/// system_parameter! {
+///     struct Combatants<'w, 's> {
+///         players: Query<'w, (&'static Player, &'static Health)>,
+///         enemies: Query<'w, (&'static Enemy, &'static Health)>,
+///     }
+/// }
+///
+/// fn count(mut combatants: Combatants) {
+///     assert_eq!(combatants.players.iter().count(), 1);
+///     assert_eq!(combatants.enemies.iter().count(), 2);
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn((Player, Health(10)));
+/// world.spawn((Enemy, Health(3)));
+/// world.spawn((Enemy, Health(4)));
+/// count.run(&world).unwrap();
+/// ```
+#[macro_export]
+macro_rules! system_parameter {
+    (
+        $(#[$attribute: meta])*
+        $visibility: vis struct $name: ident<$world: lifetime, $borrow: lifetime> {
+            $(
+                $(#[$field_attribute: meta])*
+                $field_visibility: vis $field: ident: $type: ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$attribute])*
+        $visibility struct $name<$world, $borrow> {
+            $(
+                $(#[$field_attribute])*
+                $field_visibility $field: $type,
+            )*
+            // Either lifetime may go unused by the fields, such as when they're all queries.
+            __lifetimes: std::marker::PhantomData<(&$world (), &$borrow ())>,
+        }
+
+        const _: () = {
+            use $crate::{Access, Fetch, FetchError, FetchItem, SystemParameter, World};
+
+            impl<$world, $borrow> SystemParameter for $name<$world, $borrow> {
+                type State = ($(<$type as SystemParameter>::State,)*);
+                // The struct itself, with any lifetimes, does the fetching.
+                type Fetch = $name<'static, 'static>;
+
+                fn access(access: &mut Access) {
+                    $(
+                        let mut field_access = Access::new();
+                        <$type as SystemParameter>::access(&mut field_access);
+                        access.extend_parameter(
+                            concat!(stringify!($name), ".", stringify!($field)),
+                            &field_access,
+                        );
+                    )*
+                }
+            }
+
+            impl<$world, $borrow> Fetch<$world, ($(<$type as SystemParameter>::State,)*)>
+                for $name<'static, $borrow>
+            {
+                type Item = Item<$world, $borrow>;
+
+                fn fetch(
+                    world: &$world World,
+                    state: &$world mut ($(<$type as SystemParameter>::State,)*),
+                ) -> Result<Self::Item, FetchError> {
+                    let ($($field,)*) = state;
+                    Ok(Item {
+                        $(
+                            $field: <<$type as SystemParameter>::Fetch as Fetch<
+                                $world,
+                                <$type as SystemParameter>::State,
+                            >>::fetch(world, $field)?,
+                        )*
+                        __lifetimes: std::marker::PhantomData,
+                    })
+                }
+
+                fn entity_counts(item: &Self::Item, counts: &mut Vec<usize>) {
+                    $(
+                        <<$type as SystemParameter>::Fetch as Fetch<
+                            $world,
+                            <$type as SystemParameter>::State,
+                        >>::entity_counts(&item.$field, counts);
+                    )*
+                }
+            }
+
+            $visibility struct Item<$world, $borrow> {
+                $(
+                    $field: <<$type as SystemParameter>::Fetch as Fetch<
+                        $world,
+                        <$type as SystemParameter>::State,
+                    >>::Item,
+                )*
+                __lifetimes: std::marker::PhantomData<(&$world (), &$borrow ())>,
+            }
+
+            impl<'a, $world, $borrow> FetchItem<'a> for Item<$world, $borrow> {
+                type InnerItem = $name<$world, 'a>;
+                fn inner(&'a mut self) -> Self::InnerItem {
+                    $name {
+                        $($field: self.$field.inner(),)*
+                        __lifetimes: std::marker::PhantomData,
+                    }
+                }
+            }
+        };
+    };
+}
@@ -0,0 +1,251 @@
+//! Time passing between runs of a `Schedule`, and stages that run on a fixed timestep.
+//!
+//! A `Schedule` with a `Clock` updates the `Time` resource at the start of each run.
+//! A `FixedTimestep` stage accumulates that time and runs its own schedule once for every
+//! whole step that has passed, so simulation code runs at the same rate however fast frames are.
+
+use crate::{AccessConflict, BoxedSystem, IntoSystem, Schedule, ScheduleError, SystemError, World};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A source of the current time.
+pub trait Clock: Send + Sync + 'static {
+    /// Time elapsed since the clock started.
+    fn elapsed(&self) -> Duration;
+}
+
+/// A clock that follows real time.
+pub struct RealClock {
+    start: Instant,
+}
+
+impl RealClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for RealClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for RealClock {
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A clock that only moves when it's advanced, for tests and replays.
+/// Clones share the same time, so a clone can be kept to advance a clock given to a `Schedule`.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+/// A resource with how much time has passed.
+/// Within a `FixedTimestep` stage `delta` is the stage's step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Time {
+    delta: Duration,
+    elapsed: Duration,
+}
+
+impl Time {
+    /// Time passed since the last update.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// `delta` in seconds.
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Total time passed across all updates.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Records that `delta` time has passed.
+    pub fn advance(&mut self, delta: Duration) {
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+}
+
+/// A stage that runs a schedule once for every `step` of time passed.
+/// Time is read from the world's `Time` resource and left over time is carried to the next run.
+///
+/// A `FixedTimestep` is added to a schedule like a system. It runs alone, like an exclusive system.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// # use std::time::Duration;
+/// struct Steps(u32);
+///
+/// fn physics(steps: &mut Steps) {
+///     steps.0 += 1;
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn((Steps(0),));
+///
+/// let mut physics_schedule = Schedule::new();
+/// physics_schedule.add_system(physics);
+///
+/// let clock = ManualClock::new();
+/// let mut schedule = Schedule::new();
+/// schedule
+///     .set_clock(clock.clone())
+///     .add_system(FixedTimestep::new(Duration::from_millis(10), physics_schedule));
+///
+/// clock.advance(Duration::from_millis(35));
+/// schedule.run(&mut world).unwrap();
+/// assert_eq!(world.get_single::<Steps>().unwrap().0, 3);
+///
+/// // The 5ms left over is carried to the next run.
+/// clock.advance(Duration::from_millis(5));
+/// schedule.run(&mut world).unwrap();
+/// assert_eq!(world.get_single::<Steps>().unwrap().0, 4);
+/// ```
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: Option<u32>,
+    accumulator: Duration,
+    /// The time as seen by the stage's systems, which advances a step at a time.
+    time: Time,
+    schedule: Schedule,
+}
+
+impl FixedTimestep {
+    pub fn new(step: Duration, schedule: Schedule) -> Self {
+        assert!(
+            step > Duration::ZERO,
+            "A fixed timestep must be longer than zero"
+        );
+        Self {
+            step,
+            max_steps: None,
+            accumulator: Duration::ZERO,
+            time: Time::default(),
+            schedule,
+        }
+    }
+
+    /// Limits how many steps are run at once to catch up.
+    /// If more steps are due the extra time is dropped, so a slow frame
+    /// doesn't cause more slow frames.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// # use std::time::Duration;
+    /// struct Steps(u32);
+    ///
+    /// fn physics(steps: &mut Steps) {
+    ///     steps.0 += 1;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((Steps(0),));
+    ///
+    /// let mut physics_schedule = Schedule::new();
+    /// physics_schedule.add_system(physics);
+    /// let mut fixed_timestep =
+    ///     FixedTimestep::new(Duration::from_millis(10), physics_schedule).with_max_steps(3);
+    ///
+    /// // An empty schedule with a clock just updates the world's `Time`.
+    /// let clock = ManualClock::new();
+    /// let mut time_schedule = Schedule::new();
+    /// time_schedule.set_clock(clock.clone());
+    ///
+    /// clock.advance(Duration::from_millis(55));
+    /// time_schedule.run(&mut world).unwrap();
+    /// assert_eq!(fixed_timestep.run(&mut world).unwrap(), 3);
+    /// assert_eq!(world.get_single::<Steps>().unwrap().0, 3);
+    /// // The two steps that couldn't be caught up on are dropped, but the 5ms remainder is kept.
+    /// assert_eq!(fixed_timestep.accumulator(), Duration::from_millis(5));
+    /// ```
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Time passed that is not yet enough for another step.
+    pub fn accumulator(&self) -> Duration {
+        self.accumulator
+    }
+
+    /// Runs the stage's schedule for every whole step that has passed, returning how many ran.
+    pub fn run(&mut self, world: &mut World) -> Result<u32, ScheduleError> {
+        let outer_time = world
+            .get_single::<Time>()
+            .map(|time| *time)
+            .unwrap_or_default();
+        self.accumulator += outer_time.delta();
+
+        let mut steps = 0;
+        let mut result = Ok(());
+        while self.accumulator >= self.step {
+            if self.max_steps == Some(steps) {
+                // Drop whole steps that can't be caught up on and keep the remainder.
+                self.accumulator = Duration::from_nanos(
+                    (self.accumulator.as_nanos() % self.step.as_nanos()) as u64,
+                );
+                break;
+            }
+            self.accumulator -= self.step;
+            steps += 1;
+
+            self.time.advance(self.step);
+            world.insert_resource(self.time);
+            result = self.schedule.run(world);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        // Systems after this stage see the real time again.
+        if steps > 0 {
+            world.insert_resource(outer_time);
+        }
+        result.map(|_| steps)
+    }
+}
+
+impl IntoSystem<FixedTimestep> for FixedTimestep {
+    fn try_system(mut self) -> Result<BoxedSystem, AccessConflict> {
+        Ok(BoxedSystem::exclusive(
+            std::any::type_name::<FixedTimestep>(),
+            move |world| {
+                self.run(world)
+                    .map(|_| ())
+                    .map_err(|e| SystemError::System(e.into()))
+            },
+        ))
+    }
+}
@@ -5,20 +5,40 @@
 //!         components: Vec<ComponentStore>
 //!             TypeId
 //!             ComponentVec (which can be downcast into a RwLock<Vec<T>>
+//!         `tags: Vec<TypeId>`
+//!     `HashMap<TypeId, TagPool>` (which can be downcast into a `Tags<T>`)
 //!
 //! The world contains entity metadata and archetypes.
 //! Archetypes contain Vecs of component data.
-
-use super::{Fetch, FetchError, Query, QueryFetch, QueryParameters, Single, SingleMut};
+//!
+//! Zero-sized components ("tags") carry no data so archetypes only record that they have them.
+//! The tag values themselves are kept in a single pool per type on the world so that
+//! they can be returned when removed and dropped when their entity is despawned.
+
+use super::events::update_events;
+use super::hooks::ComponentHooks;
+use super::non_send;
+use super::{
+    Access, ComponentAlreadyBorrowed, ComponentParameter, ComponentParameters, ComponentRef,
+    EntityMut, EntityRef, Entry, Event, Events, FetchError, Query, QueryParameters, Single,
+    SingleMut,
+};
 
 use std::any::{Any, TypeId};
 use std::collections::{hash_map::DefaultHasher, HashMap};
 use std::hash::{Hash, Hasher};
-use std::sync::RwLock;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+static NEXT_WORLD_ID: AtomicU64 = AtomicU64::new(0);
 
 // This can be used to easily change the size of an EntityId.
 pub(crate) type EntityId = u32;
 
+/// Anything that can be stored in the world.
 pub trait Component: Sync + Send + 'static {}
 impl<T: Sync + Send + 'static> Component for T {}
 /// The ComponentVec trait is used to define a set of things that can be done on
@@ -26,7 +46,6 @@ impl<T: Sync + Send + 'static> Component for T {}
 trait ComponentVec: Sync + Send {
     fn to_any(&self) -> &dyn Any;
     fn to_any_mut(&mut self) -> &mut dyn Any;
-    fn len(&mut self) -> usize;
     fn swap_remove(&mut self, index: EntityId);
     fn migrate(&mut self, entity_index: EntityId, other_archetype: &mut dyn ComponentVec);
     fn new_same_type(&self) -> Box<dyn ComponentVec + Send + Sync>;
@@ -40,11 +59,6 @@ impl<T: Component> ComponentVec for RwLock<Vec<T>> {
         self
     }
 
-    fn len(&mut self) -> usize {
-        // Perhaps this call to read incurs unnecessary overhead.
-        self.get_mut().unwrap().len()
-    }
-
     fn swap_remove(&mut self, index: EntityId) {
         self.get_mut().unwrap().swap_remove(index as usize);
     }
@@ -59,6 +73,140 @@ impl<T: Component> ComponentVec for RwLock<Vec<T>> {
     }
 }
 
+/// Zero-sized components are stored as tags: they have no column within an archetype.
+pub(crate) fn is_tag<T>() -> bool {
+    std::mem::size_of::<T>() == 0
+}
+
+/// The TagPool trait is used to drop a tag from an entity without knowing its exact type.
+trait TagPool: Sync + Send {
+    fn to_any(&self) -> &dyn Any;
+    fn to_any_mut(&mut self) -> &mut dyn Any;
+    fn remove_one(&mut self);
+}
+
+/// Every instance of one type of tag, shared by all the archetypes that have it.
+///
+/// Each `&mut T` needs an instance of its own, so instances are moved out of the pool
+/// while they're borrowed mutably and moved back in when the borrow ends.
+pub(crate) struct Tags<T> {
+    instances: RwLock<Vec<T>>,
+    /// How many instances are currently moved out to be borrowed mutably.
+    borrowed_mut: AtomicUsize,
+}
+
+impl<T> Tags<T> {
+    fn new() -> Self {
+        Self {
+            instances: RwLock::new(Vec::new()),
+            borrowed_mut: AtomicUsize::new(0),
+        }
+    }
+
+    fn instances_mut(&mut self) -> &mut Vec<T> {
+        self.instances.get_mut().unwrap()
+    }
+
+    /// Borrows the instances to read.
+    /// This fails while any instances are borrowed mutably.
+    pub(crate) fn read(&self) -> Option<RwLockReadGuard<'_, Vec<T>>> {
+        let instances = self.instances.try_read().ok()?;
+        if self.borrowed_mut.load(Ordering::Acquire) == 0 {
+            Some(instances)
+        } else {
+            None
+        }
+    }
+
+    /// Moves `count` instances out of the pool to borrow them mutably.
+    /// This fails while the instances are borrowed to read or if too few are left.
+    pub(crate) fn write(&self, count: usize) -> Option<TagsMut<'_, T>> {
+        let mut instances = self.instances.try_write().ok()?;
+        let remaining = instances.len().checked_sub(count)?;
+        self.borrowed_mut.fetch_add(count, Ordering::AcqRel);
+        Some(TagsMut {
+            tags: self,
+            instances: instances.split_off(remaining),
+        })
+    }
+}
+
+impl<T: Component> TagPool for Tags<T> {
+    fn to_any(&self) -> &dyn Any {
+        self
+    }
+    fn to_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn remove_one(&mut self) {
+        self.instances_mut().pop();
+    }
+}
+
+/// Instances of a tag moved out of the world's pool so they can be borrowed mutably.
+/// They're moved back into the pool when this is dropped.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// struct Frozen;
+/// struct Burning;
+///
+/// let mut world = World::new();
+/// world.spawn((Frozen, 1));
+/// world.spawn((Frozen, true));
+///
+/// let mut query = world.query::<(&mut Frozen,)>().unwrap();
+/// assert_eq!(query.iter().count(), 2);
+/// drop(query);
+///
+/// assert!(world.get_single_mut::<Frozen>().is_ok());
+/// assert!(matches!(
+///     world.get_single_mut::<Burning>(),
+///     Err(FetchError::ComponentDoesNotExist(_))
+/// ));
+/// ```
+pub struct TagsMut<'world_borrow, T> {
+    tags: &'world_borrow Tags<T>,
+    instances: Vec<T>,
+}
+
+impl<'world_borrow, T> Deref for TagsMut<'world_borrow, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        &self.instances
+    }
+}
+
+impl<'world_borrow, T> DerefMut for TagsMut<'world_borrow, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.instances
+    }
+}
+
+impl<'world_borrow, T> Drop for TagsMut<'world_borrow, T> {
+    fn drop(&mut self) {
+        let mut instances = self
+            .tags
+            .instances
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.tags
+            .borrowed_mut
+            .fetch_sub(self.instances.len(), Ordering::AcqRel);
+        instances.append(&mut self.instances);
+    }
+}
+
+fn tag_pool_mut<T: 'static>(tags: &mut HashMap<TypeId, Box<dyn TagPool>>) -> Option<&mut Vec<T>> {
+    tags.get_mut(&TypeId::of::<T>()).map(|pool| {
+        pool.to_any_mut()
+            .downcast_mut::<Tags<T>>()
+            .unwrap()
+            .instances_mut()
+    })
+}
+
 // This could be made unchecked in the future if there's a high degree of confidence in everything else.
 fn component_vec_to_mut<T: 'static>(c: &mut dyn ComponentVec) -> &mut Vec<T> {
     c.to_any_mut()
@@ -75,7 +223,7 @@ pub(crate) struct ComponentStore {
 }
 
 impl ComponentStore {
-    pub fn new<T: 'static + Send + Sync>() -> Self {
+    pub fn new<T: Component>() -> Self {
         Self {
             type_id: TypeId::of::<T>(),
             data: Box::new(RwLock::new(Vec::<T>::new())),
@@ -89,29 +237,78 @@ impl ComponentStore {
             data: self.data.new_same_type(),
         }
     }
-
-    /*
-    pub fn len(&mut self) -> usize {
-        self.data.len()
-    }
-    */
 }
 
 #[doc(hidden)]
 /// An archetype stores entities with the same set of components.
+#[derive(Default)]
 pub struct Archetype {
     pub(crate) entities: Vec<EntityId>,
     pub(crate) components: Vec<ComponentStore>,
+    /// Sorted `TypeId`s of the zero-sized components entities in this archetype have.
+    pub(crate) tags: Vec<TypeId>,
 }
 
 impl Archetype {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds storage for a component type to this archetype, keeping components and tags sorted.
+    fn insert_storage<T: Component>(&mut self) {
+        let type_id = TypeId::of::<T>();
+        if is_tag::<T>() {
+            let index = self.tags.binary_search(&type_id).unwrap_or_else(|i| i);
+            self.tags.insert(index, type_id);
+        } else {
+            let index = self
+                .components
+                .binary_search_by_key(&type_id, |c| c.type_id)
+                .unwrap_or_else(|i| i);
+            self.components.insert(index, ComponentStore::new::<T>());
+        }
+    }
+
+    fn remove_storage(&mut self, type_id: TypeId) {
+        if let Some(index) = self.component_index(type_id) {
+            self.components.remove(index);
+        } else {
+            self.tags.retain(|t| *t != type_id);
+        }
+    }
+
+    /// Creates an empty archetype with the same components and tags as self.
+    fn new_same_type(&self) -> Self {
         Self {
             entities: Vec::new(),
-            components: Vec::new(),
+            components: self.components.iter().map(|c| c.new_same_type()).collect(),
+            tags: self.tags.clone(),
         }
     }
 
+    /// Returns true if entities in this archetype have a component (or tag) of this type.
+    pub(crate) fn contains(&self, type_id: TypeId) -> bool {
+        self.component_index(type_id).is_some() || self.tags.binary_search(&type_id).is_ok()
+    }
+
+    pub(crate) fn component_index(&self, type_id: TypeId) -> Option<usize> {
+        self.components
+            .binary_search_by_key(&type_id, |c| c.type_id)
+            .ok()
+    }
+
+    /// All component and tag types in this archetype, sorted.
+    pub(crate) fn type_ids(&self) -> Vec<TypeId> {
+        let mut type_ids: Vec<TypeId> = self
+            .components
+            .iter()
+            .map(|c| c.type_id)
+            .chain(self.tags.iter().copied())
+            .collect();
+        type_ids.sort_unstable();
+        type_ids
+    }
+
     pub(crate) fn get<T: 'static>(&self, index: usize) -> &RwLock<Vec<T>> {
         self.components[index]
             .data
@@ -135,8 +332,16 @@ impl Archetype {
         component_vec_to_mut(&mut *self.components[component_index].data)
     }
 
-    fn replace_component<T: 'static>(&mut self, component_index: usize, index: EntityId, t: T) {
-        self.mutable_component_store(component_index)[index as usize] = t;
+    fn replace_component<T: 'static>(
+        &mut self,
+        component_index: usize,
+        index: EntityId,
+        t: T,
+    ) -> T {
+        std::mem::replace(
+            &mut self.mutable_component_store(component_index)[index as usize],
+            t,
+        )
     }
 
     fn push<T: 'static>(&mut self, component_index: usize, t: T) {
@@ -147,16 +352,7 @@ impl Archetype {
         &mut self,
         index: EntityId,
     ) -> Result<&mut T, EntityMissingComponent> {
-        let type_id = TypeId::of::<T>();
-        let mut component_index = None;
-        for (i, c) in self.components.iter().enumerate() {
-            if c.type_id == type_id {
-                component_index = Some(i);
-                break;
-            }
-        }
-
-        if let Some(component_index) = component_index {
+        if let Some(component_index) = self.component_index(TypeId::of::<T>()) {
             Ok(&mut self.mutable_component_store(component_index)[index as usize])
         } else {
             Err(EntityMissingComponent::new::<T>(index))
@@ -190,8 +386,8 @@ impl Archetype {
 #[derive(Debug, Clone, Copy)]
 #[doc(hidden)]
 pub struct EntityLocation {
-    archetype_index: EntityId,
-    index_in_archetype: EntityId,
+    pub(crate) archetype_index: EntityId,
+    pub(crate) index_in_archetype: EntityId,
 }
 
 #[derive(Clone, Copy)]
@@ -213,6 +409,97 @@ pub struct World {
     bundle_id_to_archetype: HashMap<u64, usize>,
     pub(crate) entities: Vec<EntityInfo>,
     free_entities: Vec<EntityId>,
+    tags: HashMap<TypeId, Box<dyn TagPool>>,
+    hooks: ComponentHooks,
+    /// Swaps the buffers of each type of event added with `add_event`.
+    event_updaters: Vec<fn(&mut World)>,
+    /// Identifies this world's non-`Send` resources.
+    id: u64,
+    /// The thread that created the world, which stores its non-`Send` resources.
+    thread: ThreadId,
+    fetch_mode: FetchMode,
+}
+
+/// What `World::query`, `World::get_single` and `World::get_single_mut` do
+/// when a component they need is already borrowed.
+/// # Example
+/// ```
+/// # use kudo::*;
+/// # use std::sync::Arc;
+/// # use std::time::Duration;
+/// struct Position(f32);
+/// struct Velocity(f32);
+///
+/// let mut world = World::new();
+/// world.spawn((Position(0.), Velocity(1.)));
+/// world.set_fetch_mode(FetchMode::Blocking { timeout: None });
+/// let world = Arc::new(world);
+///
+/// // The queries list their components in opposite orders but lock them in the same order.
+/// let other_world = world.clone();
+/// let thread = std::thread::spawn(move || {
+///     for _ in 0..100 {
+///         let mut query = other_world.query::<(&mut Velocity, &mut Position)>().unwrap();
+///         for (velocity, position) in query.iter() {
+///             position.0 += velocity.0;
+///         }
+///     }
+/// });
+/// for _ in 0..100 {
+///     let mut query = world.query::<(&mut Position, &mut Velocity)>().unwrap();
+///     for (position, velocity) in query.iter() {
+///         position.0 += velocity.0;
+///     }
+/// }
+/// thread.join().unwrap();
+/// assert_eq!(world.get_single::<Position>().unwrap().0, 200.);
+/// ```
+///
+/// A blocking fetch with a timeout gives up once the timeout has passed.
+/// ```
+/// # use kudo::*;
+/// # use std::time::Duration;
+/// struct Score(u32);
+///
+/// let mut world = World::new();
+/// world.spawn((Score(0),));
+/// world.set_fetch_mode(FetchMode::Blocking {
+///     timeout: Some(Duration::from_millis(10)),
+/// });
+///
+/// let score = world.get_single_mut::<Score>().unwrap();
+/// match world.get_single::<Score>() {
+///     Err(FetchError::ComponentAlreadyBorrowed(error)) => {
+///         assert!(error.to_string().contains("Score"));
+///     }
+///     _ => panic!("Score should still be borrowed"),
+/// }
+/// drop(score);
+/// assert!(world.get_single::<Score>().is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FetchMode {
+    /// Fail straight away with `ComponentAlreadyBorrowed`.
+    #[default]
+    Immediate,
+    /// Wait until every component is available, failing with `ComponentAlreadyBorrowed`
+    /// if that takes longer than the timeout.
+    ///
+    /// Without a timeout a fetch waits on each lock in turn, taking them in order of archetype
+    /// then component `TypeId`. Every fetch takes locks in the same order so they can't deadlock
+    /// each other, but a thread that waits while still holding an earlier borrow can.
+    ///
+    /// With a timeout, and for zero-sized tags, a fetch that finds a component borrowed
+    /// releases every lock it already took before it waits then tries again.
+    Blocking { timeout: Option<Duration> },
+}
+
+/// Whether a fetch fails or waits when a component's storage is already borrowed.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    Try,
+    Block,
 }
 
 /// This entity has been despawned so operations can no longer
@@ -256,8 +543,21 @@ impl std::error::Error for EntityMissingComponent {}
 pub enum ComponentError {
     EntityMissingComponent(EntityMissingComponent),
     NoSuchEntity(NoSuchEntity),
+    ComponentAlreadyBorrowed(ComponentAlreadyBorrowed),
 }
 
+impl std::fmt::Display for ComponentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComponentError::EntityMissingComponent(e) => e.fmt(f),
+            ComponentError::NoSuchEntity(e) => e.fmt(f),
+            ComponentError::ComponentAlreadyBorrowed(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ComponentError {}
+
 impl World {
     /// Create the world.
     pub fn new() -> Self {
@@ -266,6 +566,12 @@ impl World {
             bundle_id_to_archetype: HashMap::new(),
             entities: Vec::new(),
             free_entities: Vec::new(),
+            tags: HashMap::new(),
+            hooks: ComponentHooks::default(),
+            event_updaters: Vec::new(),
+            id: NEXT_WORLD_ID.fetch_add(1, Ordering::Relaxed),
+            thread: std::thread::current().id(),
+            fetch_mode: FetchMode::Immediate,
         }
     }
 
@@ -300,10 +606,16 @@ impl World {
 
         self.entities[index as usize] = EntityInfo {
             location,
-            generation: generation,
+            generation,
         };
 
-        Entity { index, generation }
+        let entity = Entity { index, generation };
+        if self.hooks.has_on_add() {
+            for type_id in self.archetypes[location.archetype_index as usize].type_ids() {
+                self.hooks.run_on_add(self, type_id, entity);
+            }
+        }
+        entity
     }
 
     /// Spawn an entity with just a single component.
@@ -314,19 +626,144 @@ impl World {
     /// Remove an entity and all its components from the world.
     /// An error is returned if the entity does not exist.
     pub fn despawn(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        let location = self.location(entity)?;
+        self.despawn_at(entity.index, location);
+        Ok(())
+    }
+
+    /// Removes an entity that is known to exist.
+    pub(crate) fn despawn_at(&mut self, entity_index: EntityId, location: EntityLocation) {
+        if self.hooks.has_on_remove() {
+            let entity = self.entity_at(entity_index);
+            for type_id in self.archetypes[location.archetype_index as usize].type_ids() {
+                self.hooks.run_on_remove(self, type_id, entity);
+            }
+        }
+
         // Remove an entity
         // Update swapped entity position if an entity was moved.
-        let entity_info = self.entities[entity.index as usize];
-        if entity_info.generation == entity.generation {
-            self.entities[entity.index as usize].generation += 1;
-            let moved_entity = self.archetypes[entity_info.location.archetype_index as usize]
-                .remove_entity(entity_info.location.index_in_archetype);
-            self.free_entities.push(entity.index);
+        self.entities[entity_index as usize].generation += 1;
+        let archetype = &mut self.archetypes[location.archetype_index as usize];
+        let moved_entity = archetype.remove_entity(location.index_in_archetype);
 
-            // Update the position of an entity that was moved.
-            self.entities[moved_entity as usize].location = entity_info.location;
+        // Tags aren't stored in the archetype so drop one from each of the world's pools.
+        for type_id in archetype.tags.iter() {
+            self.tags.get_mut(type_id).unwrap().remove_one();
+        }
+        self.free_entities.push(entity_index);
 
-            Ok(())
+        // Update the position of an entity that was moved.
+        self.entities[moved_entity as usize].location = location;
+    }
+
+    /// Gets read-only access to a single component on an `Entity`.
+    /// The component's storage stays borrowed until the returned `ComponentRef` is dropped,
+    /// so this can be used while other read-only queries or systems are running.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// let mut world = World::new();
+    /// let entity = world.spawn((456, true));
+    /// let number = world.get::<i32>(entity).unwrap();
+    /// assert_eq!(*number, 456);
+    /// ```
+    pub fn get<T: 'static>(&self, entity: Entity) -> Result<ComponentRef<'_, T>, ComponentError> {
+        let location = self
+            .location(entity)
+            .map_err(ComponentError::NoSuchEntity)?;
+        <&T>::get_component(self, entity.index, location)
+    }
+
+    /// Borrows multiple components of an `Entity` at once.
+    /// The '&' before each component requests read-only access to the component.
+    /// Using '&mut' would request write/read access for that component.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// let mut world = World::new();
+    /// let entity = world.spawn((456, true));
+    /// let (number, mut b) = world.get_many::<(&i32, &mut bool)>(entity).unwrap();
+    /// *b = *number > 0;
+    /// ```
+    pub fn get_many<'world_borrow, T: ComponentParameters<'world_borrow>>(
+        &'world_borrow self,
+        entity: Entity,
+    ) -> Result<T::Item, ComponentError> {
+        let location = self
+            .location(entity)
+            .map_err(ComponentError::NoSuchEntity)?;
+        T::get_components(self, entity.index, location)
+    }
+
+    /// Gets a view of an entity that can read and write its components
+    /// without looking the entity up again for each access.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// let mut world = World::new();
+    /// let entity = world.spawn((456, true));
+    /// let entity_ref = world.entity(entity).unwrap();
+    /// if entity_ref.contains::<bool>() {
+    ///     *entity_ref.get_mut::<i32>().unwrap() += 1;
+    /// }
+    /// ```
+    pub fn entity(&self, entity: Entity) -> Result<EntityRef<'_>, NoSuchEntity> {
+        let location = self.location(entity)?;
+        Ok(EntityRef::new(self, entity, location))
+    }
+
+    /// Gets a view of an entity that can also add and remove components or despawn the entity.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// let mut world = World::new();
+    /// let entity = world.spawn((456,));
+    /// let mut entity_mut = world.entity_mut(entity).unwrap();
+    /// entity_mut.insert((true, "Medusa"));
+    /// let b = entity_mut.remove::<bool>().unwrap();
+    /// *entity_mut.get_mut::<i32>().unwrap() += 1;
+    /// ```
+    pub fn entity_mut(&mut self, entity: Entity) -> Result<EntityMut<'_>, NoSuchEntity> {
+        let location = self.location(entity)?;
+        Ok(EntityMut::new(self, entity, location))
+    }
+
+    /// Gets the entry for a component of an entity, for in-place modification
+    /// or insertion when the component is missing.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Counter(u32);
+    /// let mut world = World::new();
+    /// let entity = world.spawn((true,));
+    ///
+    /// for _ in 0..3 {
+    ///     world
+    ///         .entry::<Counter>(entity)
+    ///         .unwrap()
+    ///         .and_modify(|counter| counter.0 += 1)
+    ///         .or_insert(Counter(1));
+    /// }
+    /// assert_eq!(world.get::<Counter>(entity).unwrap().0, 3);
+    /// ```
+    pub fn entry<T: Component>(&mut self, entity: Entity) -> Result<Entry<'_, T>, NoSuchEntity> {
+        let location = self.location(entity)?;
+        Ok(Entry::new(self, entity.index, location))
+    }
+
+    /// The handle of an entity that is known to exist.
+    fn entity_at(&self, entity_index: EntityId) -> Entity {
+        Entity {
+            index: entity_index,
+            generation: self.entities[entity_index as usize].generation,
+        }
+    }
+
+    /// Finds where an entity's components are stored.
+    pub(crate) fn location(&self, entity: Entity) -> Result<EntityLocation, NoSuchEntity> {
+        let entity_info = self.entities[entity.index as usize];
+        if entity_info.generation == entity.generation {
+            Ok(entity_info.location)
         } else {
             Err(NoSuchEntity)
         }
@@ -337,15 +774,26 @@ impl World {
         &mut self,
         entity: Entity,
     ) -> Result<&mut T, ComponentError> {
-        let entity_info = self.entities[entity.index as usize];
-        if entity_info.generation == entity.generation {
-            let archetype = &mut self.archetypes[entity_info.location.archetype_index as usize];
-            archetype
-                .get_component_mut(entity_info.location.index_in_archetype)
-                .map_err(|e| ComponentError::EntityMissingComponent(e))
+        let location = self
+            .location(entity)
+            .map_err(ComponentError::NoSuchEntity)?;
+        self.get_component_mut_at(entity.index, location)
+            .map_err(ComponentError::EntityMissingComponent)
+    }
+
+    pub(crate) fn get_component_mut_at<T: 'static>(
+        &mut self,
+        entity_index: EntityId,
+        location: EntityLocation,
+    ) -> Result<&mut T, EntityMissingComponent> {
+        let archetype = &mut self.archetypes[location.archetype_index as usize];
+        if is_tag::<T>() && archetype.contains(TypeId::of::<T>()) {
+            // Every instance of a tag is the same so any of them can be handed out.
+            Ok(&mut tag_pool_mut::<T>(&mut self.tags).unwrap()[0])
         } else {
-            // Entity no longer exists
-            Err(ComponentError::NoSuchEntity(NoSuchEntity))
+            archetype
+                .get_component_mut(location.index_in_archetype)
+                .map_err(|_| EntityMissingComponent::new::<T>(entity_index))
         }
     }
 
@@ -359,251 +807,499 @@ impl World {
     /// let b = world.remove_component::<bool>(entity).unwrap();
     /// ```
     pub fn remove_component<T: 'static>(&mut self, entity: Entity) -> Result<T, ComponentError> {
-        let entity_info = self.entities[entity.index as usize];
-
-        if entity_info.generation == entity.generation {
-            let current_archetype = &self.archetypes[entity_info.location.archetype_index as usize];
-
-            let type_id = TypeId::of::<T>();
-            let mut type_ids: Vec<TypeId> = current_archetype
-                .components
-                .iter()
-                .map(|c| c.type_id)
-                .collect();
-            let binary_search_index = type_ids.binary_search(&type_id);
-
-            if let Ok(remove_index) = binary_search_index {
-                type_ids.remove(remove_index);
-                let bundle_id = calculate_bundle_id(&type_ids);
-                let new_archetype_index = if let Some(new_archetype_index) =
-                    self.bundle_id_to_archetype.get(&bundle_id)
-                {
-                    *new_archetype_index
-                } else {
-                    // Create a new archetype
-                    let mut archetype = Archetype::new();
-                    for c in current_archetype.components.iter() {
-                        if c.type_id != type_id {
-                            archetype.components.push(c.new_same_type());
-                        }
-                    }
-
-                    let new_archetype_index = self.archetypes.len();
-
-                    self.bundle_id_to_archetype
-                        .insert(bundle_id, new_archetype_index);
-                    self.archetypes.push(archetype);
-                    new_archetype_index
-                };
-
-                // Much of this code is similar to the code for adding a component.
-
-                // index_twice lets us mutably borrow from the world twice.
-                let (old_archetype, new_archetype) = index_twice(
-                    &mut self.archetypes,
-                    entity_info.location.archetype_index as usize,
-                    new_archetype_index,
-                );
-
-                // If an entity is being moved then update its location
-                if let Some(last) = old_archetype.entities.last() {
-                    self.entities[*last as usize].location = entity_info.location;
-                }
-
-                // First update the entity's location to reflect the changes about to be made.
-                self.entities[entity.index as usize].location = EntityLocation {
-                    archetype_index: new_archetype_index as EntityId,
-                    index_in_archetype: (new_archetype.len()) as EntityId,
-                };
-
-                // The new archetype is the same as the old one but with one fewer components.
-                for i in 0..remove_index {
-                    old_archetype.migrate_component(
-                        i,
-                        entity_info.location.index_in_archetype,
-                        new_archetype,
-                        i,
-                    );
-                }
-
-                let components_in_archetype = old_archetype.components.len();
-
-                for i in (remove_index + 1)..components_in_archetype {
-                    old_archetype.migrate_component(
-                        i,
-                        entity_info.location.index_in_archetype,
-                        new_archetype,
-                        i - 1,
-                    );
-                }
-
-                old_archetype
-                    .entities
-                    .swap_remove(entity_info.location.index_in_archetype as usize);
-                new_archetype.entities.push(entity.index);
+        let location = self
+            .location(entity)
+            .map_err(ComponentError::NoSuchEntity)?;
+        self.remove_component_at(entity.index, location)
+            .map(|(component, _)| component)
+            .map_err(ComponentError::EntityMissingComponent)
+    }
 
-                Ok(
-                    component_vec_to_mut::<T>(&mut *old_archetype.components[remove_index].data)
-                        .swap_remove(entity_info.location.index_in_archetype as usize),
-                )
+    /// Removes a component from an entity that is known to exist.
+    /// The component and the entity's new location are returned.
+    pub(crate) fn remove_component_at<T: 'static>(
+        &mut self,
+        entity_index: EntityId,
+        location: EntityLocation,
+    ) -> Result<(T, EntityLocation), EntityMissingComponent> {
+        let type_id = TypeId::of::<T>();
+        let current_archetype_index = location.archetype_index as usize;
+
+        if self.archetypes[current_archetype_index].contains(type_id) {
+            // Hooks run while the entity still has the component.
+            self.hooks
+                .run_on_remove(self, type_id, self.entity_at(entity_index));
+            let current_archetype = &mut self.archetypes[current_archetype_index];
+
+            // Take the component out first.
+            // Its column is now one shorter than the others and will not be migrated.
+            let component = if is_tag::<T>() {
+                tag_pool_mut::<T>(&mut self.tags).unwrap().pop().unwrap()
             } else {
-                // Component is not in entity
-                Err(ComponentError::EntityMissingComponent(
-                    EntityMissingComponent::new::<T>(entity.index),
-                ))
-            }
+                let remove_index = current_archetype.component_index(type_id).unwrap();
+                current_archetype
+                    .mutable_component_store::<T>(remove_index)
+                    .swap_remove(location.index_in_archetype as usize)
+            };
+
+            // The new archetype is the same as the old one but with one fewer components.
+            let mut type_ids = current_archetype.type_ids();
+            type_ids.retain(|t| *t != type_id);
+            let new_archetype_index =
+                self.archetype_for(&type_ids, current_archetype_index, |archetype| {
+                    archetype.remove_storage(type_id)
+                });
+
+            let new_location = self.migrate_entity(entity_index, location, new_archetype_index);
+            Ok((component, new_location))
         } else {
-            // Entity is not in world
-            Err(ComponentError::NoSuchEntity(NoSuchEntity))
+            // Component is not in entity
+            Err(EntityMissingComponent::new::<T>(entity_index))
         }
     }
 
     /// Adds a component to an entity.
     /// If the component already exists its data will be replaced.
-    pub fn add_component<T: 'static + Send + Sync>(
+    pub fn add_component<T: Component>(
         &mut self,
         entity: Entity,
         t: T,
     ) -> Result<(), NoSuchEntity> {
+        self.insert_component(entity, t).map(|_| ())
+    }
+
+    /// Adds a component to an entity.
+    /// If the component already exists it is replaced and the previous value is returned.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// let mut world = World::new();
+    /// let entity = world.spawn((456,));
+    /// assert_eq!(world.insert_component(entity, true).unwrap(), None);
+    /// assert_eq!(world.insert_component(entity, false).unwrap(), Some(true));
+    /// ```
+    pub fn insert_component<T: Component>(
+        &mut self,
+        entity: Entity,
+        t: T,
+    ) -> Result<Option<T>, NoSuchEntity> {
+        let location = self.location(entity)?;
+        let (previous, _) = self.insert_component_at(entity.index, location, t);
+        Ok(previous)
+    }
+
+    /// Replaces a component an entity already has and returns the previous value.
+    /// Unlike `insert_component` this never moves the entity to another archetype.
+    pub fn replace_component<T: 'static>(
+        &mut self,
+        entity: Entity,
+        t: T,
+    ) -> Result<T, ComponentError> {
+        Ok(std::mem::replace(self.get_component_mut(entity)?, t))
+    }
+
+    /// Takes a component's value, leaving `T::default()` in its place.
+    /// Unlike `remove_component` the entity keeps the component.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// let mut world = World::new();
+    /// let entity = world.spawn((vec![1, 2, 3],));
+    /// let numbers = world.take_component::<Vec<i32>>(entity).unwrap();
+    /// assert_eq!(numbers.len(), 3);
+    /// assert!(world.get::<Vec<i32>>(entity).unwrap().is_empty());
+    /// ```
+    pub fn take_component<T: 'static + Default>(
+        &mut self,
+        entity: Entity,
+    ) -> Result<T, ComponentError> {
+        Ok(std::mem::take(self.get_component_mut(entity)?))
+    }
+
+    /// Adds a component to an entity that is known to exist.
+    /// The previous value of the component, if any, and the entity's new location are returned.
+    pub(crate) fn insert_component_at<T: Component>(
+        &mut self,
+        entity_index: EntityId,
+        location: EntityLocation,
+        t: T,
+    ) -> (Option<T>, EntityLocation) {
         // In an archetypal ECS adding and removing components are the most expensive operations.
-        // The volume of code in this function reflects that.
         // When a component is added the entity can be either migrated to a brand new archetype
         // or migrated to an existing archetype.
+        let type_id = TypeId::of::<T>();
+        let current_archetype_index = location.archetype_index as usize;
 
-        // First find if the entity exists
-        let entity_info = self.entities[entity.index as usize];
-        if entity_info.generation == entity.generation {
-            let type_id = TypeId::of::<T>();
-
-            // First check if the component already exists for this entity.
-            let current_archetype = &self.archetypes[entity_info.location.archetype_index as usize];
+        // First check if the component already exists for this entity.
+        let current_archetype = &mut self.archetypes[current_archetype_index];
 
-            let mut type_ids: Vec<TypeId> = current_archetype
-                .components
-                .iter()
-                .map(|c| c.type_id)
-                .collect();
-            let binary_search_index = type_ids.binary_search(&type_id);
-
-            if let Ok(insert_index) = binary_search_index {
-                // The component already exists, replace it.
-                let current_archetype =
-                    &mut self.archetypes[entity_info.location.archetype_index as usize];
-
-                // Replace the existing component.
-                current_archetype.replace_component(
-                    insert_index,
-                    entity_info.location.index_in_archetype,
-                    t,
-                );
+        if current_archetype.contains(type_id) {
+            // The component already exists, replace it.
+            let previous = if let Some(component_index) = current_archetype.component_index(type_id)
+            {
+                current_archetype.replace_component(component_index, location.index_in_archetype, t)
             } else {
-                // The component does not already exist in the current archetype.
-                // Find an existing archetype to migrate to or create a new archetype
+                std::mem::replace(&mut tag_pool_mut::<T>(&mut self.tags).unwrap()[0], t)
+            };
+            (Some(previous), location)
+        } else {
+            // The component does not already exist in the current archetype.
+            // Find an existing archetype to migrate to or create a new archetype
+            // with the structure of the current archetype and one additional component.
+            let mut type_ids = current_archetype.type_ids();
+            let insert_index = type_ids.binary_search(&type_id).unwrap_err();
+            type_ids.insert(insert_index, type_id);
+
+            let new_archetype_index =
+                self.archetype_for(&type_ids, current_archetype_index, |archetype| {
+                    archetype.insert_storage::<T>()
+                });
+
+            let new_location = self.migrate_entity(entity_index, location, new_archetype_index);
+
+            // Push the new component to the new archetype
+            self.push_component(new_archetype_index, t);
+            self.hooks
+                .run_on_add(self, type_id, self.entity_at(entity_index));
+            (None, new_location)
+        }
+    }
 
-                let insert_index = binary_search_index.unwrap_or_else(|i| i);
+    /// Finds the archetype for a sorted set of component types.
+    /// If it does not exist yet it is created by copying the structure of the archetype
+    /// at `similar_archetype` and then calling `edit` on it.
+    fn archetype_for(
+        &mut self,
+        type_ids: &[TypeId],
+        similar_archetype: usize,
+        edit: impl FnOnce(&mut Archetype),
+    ) -> usize {
+        let bundle_id = calculate_bundle_id(type_ids);
+        if let Some(archetype_index) = self.bundle_id_to_archetype.get(&bundle_id) {
+            *archetype_index
+        } else {
+            let mut archetype = self.archetypes[similar_archetype].new_same_type();
+            edit(&mut archetype);
+
+            let archetype_index = self.archetypes.len();
+            self.bundle_id_to_archetype
+                .insert(bundle_id, archetype_index);
+            self.archetypes.push(archetype);
+            archetype_index
+        }
+    }
 
-                type_ids.insert(insert_index, type_id);
-                let bundle_id = calculate_bundle_id(&type_ids);
+    /// Moves all of an entity's components that the new archetype shares with its current archetype.
+    /// Components the new archetype does not have must already be taken out by the caller
+    /// and components only the new archetype has must be pushed afterwards.
+    fn migrate_entity(
+        &mut self,
+        entity_index: EntityId,
+        location: EntityLocation,
+        new_archetype_index: usize,
+    ) -> EntityLocation {
+        // index_twice lets us mutably borrow from the world twice.
+        let (old_archetype, new_archetype) = index_twice(
+            &mut self.archetypes,
+            location.archetype_index as usize,
+            new_archetype_index,
+        );
 
-                let new_archetype_index = if let Some(new_archetype_index) =
-                    self.bundle_id_to_archetype.get(&bundle_id)
-                {
-                    // Found an existing archetype to migrate data to
-                    *new_archetype_index
-                } else {
-                    // Create a new archetype with the structure of the current archetype and one additional component.
-                    let mut archetype = Archetype::new();
-                    for c in current_archetype.components.iter() {
-                        archetype.components.push(c.new_same_type());
-                    }
+        // If an entity is being moved then update its location
+        if let Some(last) = old_archetype.entities.last() {
+            self.entities[*last as usize].location = location;
+        }
 
-                    let new_archetype_index = self.archetypes.len();
-                    archetype
-                        .components
-                        .insert(insert_index, ComponentStore::new::<T>());
-                    self.bundle_id_to_archetype
-                        .insert(bundle_id, new_archetype_index);
+        // First update the entity's location to reflect the changes about to be made.
+        let new_location = EntityLocation {
+            archetype_index: new_archetype_index as EntityId,
+            index_in_archetype: (new_archetype.len()) as EntityId,
+        };
+        self.entities[entity_index as usize].location = new_location;
+
+        for i in 0..old_archetype.components.len() {
+            if let Some(other_index) =
+                new_archetype.component_index(old_archetype.components[i].type_id)
+            {
+                old_archetype.migrate_component(
+                    i,
+                    location.index_in_archetype,
+                    new_archetype,
+                    other_index,
+                );
+            }
+        }
 
-                    self.archetypes.push(archetype);
+        old_archetype
+            .entities
+            .swap_remove(location.index_in_archetype as usize);
+        new_archetype.entities.push(entity_index);
+        new_location
+    }
 
-                    new_archetype_index
-                };
+    /// Pushes a component for the most recently added entity of an archetype.
+    fn push_component<T: Component>(&mut self, archetype_index: usize, t: T) {
+        if is_tag::<T>() {
+            self.tags
+                .entry(TypeId::of::<T>())
+                .or_insert_with(|| Box::new(Tags::<T>::new()));
+            tag_pool_mut::<T>(&mut self.tags).unwrap().push(t);
+        } else {
+            let archetype = &mut self.archetypes[archetype_index];
+            let component_index = archetype.component_index(TypeId::of::<T>()).unwrap();
+            archetype.push(component_index, t);
+        }
+    }
 
-                // index_twice lets us mutably borrow from the world twice.
-                let (old_archetype, new_archetype) = index_twice(
-                    &mut self.archetypes,
-                    entity_info.location.archetype_index as usize,
-                    new_archetype_index,
-                );
+    /// Every instance of a tag stored in the world.
+    /// This holds an instance for each entity with the tag.
+    pub(crate) fn tag_pool<T: 'static>(&self) -> Option<&Tags<T>> {
+        self.tags
+            .get(&TypeId::of::<T>())
+            .map(|pool| pool.to_any().downcast_ref::<Tags<T>>().unwrap())
+    }
 
-                // If an entity is being moved then update its location
-                if let Some(last) = old_archetype.entities.last() {
-                    self.entities[*last as usize].location = entity_info.location;
-                }
+    /// Query for an immutable reference to the first instance of a component found.
+    pub fn get_single<T: 'static>(&self) -> Result<Single<'_, T>, FetchError> {
+        self.fetch_with_mode(|lock_mode| Single::fetch(self, lock_mode))
+    }
 
-                // First update the entity's location to reflect the changes about to be made.
-                self.entities[entity.index as usize].location = EntityLocation {
-                    archetype_index: new_archetype_index as EntityId,
-                    index_in_archetype: (new_archetype.len()) as EntityId,
-                };
+    /// Query for a mutable reference to the first instance of a component found.
+    pub fn get_single_mut<T: 'static>(&self) -> Result<SingleMut<'_, T>, FetchError> {
+        self.fetch_with_mode(|lock_mode| SingleMut::fetch(self, lock_mode))
+    }
 
-                // The new archetype is the same as the old one but with one additional component.
-                for i in 0..insert_index {
-                    old_archetype.migrate_component(
-                        i,
-                        entity_info.location.index_in_archetype,
-                        new_archetype,
-                        i,
-                    );
-                }
+    /// Get a query from the world.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// # let mut world = World::new();
+    /// let query = world.query::<(&bool, &String)>();
+    /// ```
+    ///
+    /// A query can't both read and write the same component.
+    /// ```
+    /// # use kudo::*;
+    /// struct Health(u32);
+    ///
+    /// let world = World::new();
+    /// let error = world.query::<(&mut Health, &Health)>().err().unwrap();
+    /// assert!(matches!(error, FetchError::AccessConflict(_)));
+    /// let message = error.to_string();
+    /// assert!(message.starts_with("`&mut "));
+    /// assert!(message.contains("Health` writes ["));
+    /// assert!(message.ends_with("Health` reads it"));
+    /// ```
+    pub fn query<'world_borrow, T: QueryParameters>(
+        &'world_borrow self,
+    ) -> Result<Query<'world_borrow, T>, FetchError> {
+        // A query that conflicts with itself would always fail to borrow, or worse, only sometimes.
+        let mut access = Access::new();
+        T::access(&mut access);
+        if let Some(conflict) = access.conflict() {
+            return Err(FetchError::AccessConflict(conflict));
+        }
 
-                // Push the new component to the new archetype
-                new_archetype.push(insert_index, t);
+        self.fetch_with_mode(|lock_mode| Query::fetch(self, lock_mode))
+    }
 
-                let components_in_archetype = old_archetype.components.len();
+    /// Sets how fetches from the world behave when a component is already borrowed.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// # use std::sync::Arc;
+    /// # use std::time::Duration;
+    /// struct Score(u32);
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((Score(0),));
+    /// world.set_fetch_mode(FetchMode::Blocking {
+    ///     timeout: Some(Duration::from_secs(1)),
+    /// });
+    /// let world = Arc::new(world);
+    ///
+    /// let threads: Vec<_> = (0..4)
+    ///     .map(|_| {
+    ///         let world = world.clone();
+    ///         std::thread::spawn(move || {
+    ///             // Waits for the other threads instead of failing.
+    ///             world.get_single_mut::<Score>().unwrap().0 += 1;
+    ///         })
+    ///     })
+    ///     .collect();
+    /// for thread in threads {
+    ///     thread.join().unwrap();
+    /// }
+    /// assert_eq!(world.get_single::<Score>().unwrap().0, 4);
+    /// ```
+    pub fn set_fetch_mode(&mut self, fetch_mode: FetchMode) {
+        self.fetch_mode = fetch_mode;
+    }
 
-                for i in insert_index..components_in_archetype {
-                    old_archetype.migrate_component(
-                        i,
-                        entity_info.location.index_in_archetype,
-                        new_archetype,
-                        i + 1,
-                    );
-                }
+    pub fn fetch_mode(&self) -> FetchMode {
+        self.fetch_mode
+    }
 
-                old_archetype
-                    .entities
-                    .swap_remove(entity_info.location.index_in_archetype as usize);
-                new_archetype.entities.push(entity.index);
-            }
+    /// Runs `fetch`, and if the fetch mode is blocking runs it again
+    /// for as long as it fails because a component is already borrowed.
+    /// Without a timeout `fetch` waits on locks itself, so only tags are tried again.
+    fn fetch_with_mode<R>(
+        &self,
+        mut fetch: impl FnMut(LockMode) -> Result<R, FetchError>,
+    ) -> Result<R, FetchError> {
+        let (timeout, lock_mode) = match self.fetch_mode {
+            FetchMode::Immediate => return fetch(LockMode::Try),
+            FetchMode::Blocking { timeout: None } => (None, LockMode::Block),
+            FetchMode::Blocking { timeout } => (timeout, LockMode::Try),
+        };
 
-            Ok(())
-        } else {
-            Err(NoSuchEntity)
+        let start = Instant::now();
+        let mut wait = Duration::from_micros(10);
+        loop {
+            match fetch(lock_mode) {
+                Err(FetchError::ComponentAlreadyBorrowed(e)) => {
+                    if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+                        return Err(FetchError::ComponentAlreadyBorrowed(e));
+                    }
+                    std::thread::sleep(wait);
+                    wait = (wait * 2).min(Duration::from_millis(1));
+                }
+                result => return result,
+            }
         }
     }
 
-    /// Query for an immutable reference to the first instance of a component found.
-    pub fn get_single<T: 'static>(&self) -> Result<Single<T>, FetchError> {
-        <&T>::fetch(self)
+    /// Adds a resource, which is a component with a single instance,
+    /// replacing any existing resource of the same type.
+    /// Resources are accessed by systems with `&T` and `&mut T` parameters.
+    /// # Example
+    /// ```
+    /// # use kudo::*;
+    /// struct Paused;
+    ///
+    /// let mut world = World::new();
+    /// world.insert_resource(Paused);
+    /// world.insert_resource(Paused);
+    /// assert_eq!(world.query::<(&Paused,)>().unwrap().len(), 1);
+    /// ```
+    pub fn insert_resource<T: Component>(&mut self, resource: T) {
+        let type_id = TypeId::of::<T>();
+        let existing = self
+            .archetypes
+            .iter()
+            .position(|archetype| !archetype.entities.is_empty() && archetype.contains(type_id));
+
+        if let Some(archetype_index) = existing {
+            let location = EntityLocation {
+                archetype_index: archetype_index as EntityId,
+                index_in_archetype: 0,
+            };
+            let entity_index = self.archetypes[archetype_index].entities[0];
+            *self
+                .get_component_mut_at::<T>(entity_index, location)
+                .unwrap() = resource;
+        } else {
+            self.spawn((resource,));
+        }
     }
 
-    /// Query for a mutable reference to the first instance of a component found.
-    pub fn get_single_mut<T: 'static>(&self) -> Result<SingleMut<T>, FetchError> {
-        <&mut T>::fetch(self)
+    /// Adds the `Events<E>` resource so `E` can be sent with an `EventWriter`
+    /// and read with an `EventReader`. Does nothing if it was already added.
+    pub fn add_event<E: Event>(&mut self) {
+        if self.get_single::<Events<E>>().is_ok() {
+            return;
+        }
+        self.spawn((Events::<E>::default(),));
+        self.event_updaters.push(update_events::<E>);
     }
 
-    /// Get a query from the world.
+    /// Adds a hook that runs after a `T` is added to an entity,
+    /// either by spawning the entity or by inserting a `T` it didn't have.
+    /// Replacing an entity's `T` doesn't run the hook.
     /// # Example
     /// ```
     /// # use kudo::*;
-    /// # let mut world = World::new();
-    /// let query = world.query<(&bool, &String)>();
+    /// struct Name(&'static str);
+    ///
+    /// #[derive(Default)]
+    /// struct Names(Vec<Entity>);
+    ///
+    /// let mut world = World::new();
+    /// world.insert_resource(Names::default());
+    /// world.on_add::<Name>(|world, entity| {
+    ///     world.get_single_mut::<Names>().unwrap().0.push(entity);
+    /// });
+    /// world.on_remove::<Name>(|world, entity| {
+    ///     // The entity still has its `Name` while the hook runs.
+    ///     assert!(world.get::<Name>(entity).is_ok());
+    ///     world.get_single_mut::<Names>().unwrap().0.retain(|e| *e != entity);
+    /// });
+    ///
+    /// let medusa = world.spawn((Name("Medusa"),));
+    /// let perseus = world.spawn((true,));
+    /// world.insert_component(perseus, Name("Perseus")).unwrap();
+    /// assert_eq!(world.get_single::<Names>().unwrap().0, vec![medusa, perseus]);
+    ///
+    /// world.despawn(medusa).unwrap();
+    /// assert_eq!(world.get_single::<Names>().unwrap().0, vec![perseus]);
     /// ```
-    pub fn query<'world_borrow, T: QueryParameters>(
-        &'world_borrow self,
-    ) -> Result<Query<T>, FetchError> {
-        Ok(QueryFetch::<T>::fetch(self)?.take().unwrap())
+    pub fn on_add<T: Component>(&mut self, hook: impl Fn(&World, Entity) + Send + Sync + 'static) {
+        self.hooks.add_on_add(TypeId::of::<T>(), Box::new(hook));
+    }
+
+    /// Adds a hook that runs before a `T` is removed from an entity,
+    /// either by removing the component or by despawning the entity.
+    pub fn on_remove<T: Component>(
+        &mut self,
+        hook: impl Fn(&World, Entity) + Send + Sync + 'static,
+    ) {
+        self.hooks.add_on_remove(TypeId::of::<T>(), Box::new(hook));
+    }
+
+    /// Adds a resource that isn't `Send`, replacing any existing one of the same type.
+    /// It can be accessed with the `NonSend` and `NonSendMut` system parameters,
+    /// but only from the thread that created the world.
+    /// # Panics
+    /// Panics if called from a thread other than the one that created the world.
+    pub fn insert_non_send<T: 'static>(&mut self, resource: T) {
+        assert!(
+            std::thread::current().id() == self.thread,
+            "[{}] is not Send and can only be added from the thread that created the world",
+            std::any::type_name::<T>()
+        );
+        non_send::insert(self.id, resource);
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub(crate) fn thread(&self) -> ThreadId {
+        self.thread
+    }
+
+    /// Updates every type of event added with `add_event`.
+    /// This should be called once per frame. Events are dropped after two updates.
+    pub fn update_events(&mut self) {
+        for updater in self.event_updaters.clone() {
+            updater(self);
+        }
+    }
+}
+
+impl Drop for World {
+    fn drop(&mut self) {
+        // Non-`Send` resources can only be dropped on the thread that created the world.
+        // If the world is dropped elsewhere they're dropped when that thread exits.
+        if std::thread::current().id() == self.thread {
+            non_send::remove_world(self.id);
+        }
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -614,6 +1310,13 @@ pub trait ComponentBundle: 'static + Send + Sync {
     fn new_archetype(&self) -> Archetype;
     #[doc(hidden)]
     fn spawn_in_world(self, world: &mut World, entity_index: EntityId) -> EntityLocation;
+    #[doc(hidden)]
+    fn add_to_entity(
+        self,
+        world: &mut World,
+        entity_index: EntityId,
+        location: EntityLocation,
+    ) -> EntityLocation;
 }
 
 fn calculate_bundle_id(types: &[TypeId]) -> u64 {
@@ -623,29 +1326,22 @@ fn calculate_bundle_id(types: &[TypeId]) -> u64 {
 }
 
 macro_rules! component_bundle_impl {
-    ($count: expr, $(($name: ident, $index: tt)),*) => {
-        impl< $($name: 'static + Send + Sync),*> ComponentBundle for ($($name,)*) {
+    ($(($name: ident, $index: tt)),*) => {
+        impl< $($name: Component),*> ComponentBundle for ($($name,)*) {
             fn new_archetype(&self) -> Archetype {
-                let mut components = vec![$(ComponentStore::new::<$name>()), *];
-                components.sort_unstable_by(|a, b| a.type_id.cmp(&b.type_id));
-                Archetype { components, entities: Vec::new() }
+                let mut archetype = Archetype::new();
+                $(archetype.insert_storage::<$name>();)*
+                archetype
             }
 
             fn spawn_in_world(self, world: &mut World, entity_index: EntityId) -> EntityLocation {
-                let mut types = [$(($index, TypeId::of::<$name>())), *];
-                types.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+                let mut types = [$(TypeId::of::<$name>()), *];
+                types.sort_unstable();
                 debug_assert!(
-                    types.windows(2).all(|x| x[0].1 != x[1].1),
+                    types.windows(2).all(|x| x[0] != x[1]),
                     "`ComponentBundle`s cannot have duplicate types"
                 );
 
-                // Is there a better way to map the original ordering to the sorted ordering?
-                let mut order = [0; $count];
-                for i in 0..order.len() {
-                    order[types[i].0] = i;
-                }
-                let types = [$(types[$index].1), *];
-
                 let bundle_id = calculate_bundle_id(&types);
 
                 // Find the appropriate archetype
@@ -662,28 +1358,33 @@ macro_rules! component_bundle_impl {
                 };
 
                 world.archetypes[archetype_index].entities.push(entity_index);
-                $(world.archetypes[archetype_index].push(order[$index], self.$index);)*
+                $(world.push_component(archetype_index, self.$index);)*
                 EntityLocation {
                     archetype_index: archetype_index as EntityId,
                     index_in_archetype: (world.archetypes[archetype_index].len() - 1) as EntityId
                 }
             }
+
+            fn add_to_entity(self, world: &mut World, entity_index: EntityId, location: EntityLocation) -> EntityLocation {
+                $(let (_, location) = world.insert_component_at(entity_index, location, self.$index);)*
+                location
+            }
         }
     }
 }
 
-component_bundle_impl! {1, (A, 0)}
-component_bundle_impl! {2, (A, 0), (B, 1)}
-component_bundle_impl! {3, (A, 0), (B, 1), (C, 2)}
-component_bundle_impl! {4, (A, 0), (B, 1), (C, 2), (D, 3)}
-component_bundle_impl! {5, (A, 0), (B, 1), (C, 2), (D, 3), (E, 4)}
-component_bundle_impl! {6, (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5)}
-component_bundle_impl! {7, (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6)}
-component_bundle_impl! {8, (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7)}
-component_bundle_impl! {9, (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8)}
-component_bundle_impl! {10, (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9)}
-component_bundle_impl! {11, (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10)}
-component_bundle_impl! {12, (A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10), (L, 11)}
+component_bundle_impl! {(A, 0)}
+component_bundle_impl! {(A, 0), (B, 1)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2), (D, 3)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10)}
+component_bundle_impl! {(A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6), (H, 7), (I, 8), (J, 9), (K, 10), (L, 11)}
 
 /// A helper to get two mutable borrows from the same slice.
 fn index_twice<T>(slice: &mut [T], first: usize, second: usize) -> (&mut T, &mut T) {